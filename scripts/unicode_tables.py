@@ -0,0 +1,70 @@
+#!/usr/bin/env python3
+# Régénère les tables de src/unicode.rs à partir du module unicodedata de Python, dont la
+# version de l'UCD doit être celle annoncée dans ce fichier (Python 3.11 : Unicode 14.0.0).
+# Usage : python3 scripts/unicode_tables.py ; sa sortie remplace les trois tables statiques de src/unicode.rs.
+import sys
+import unicodedata
+
+UNICODE_VERSION = "14.0.0"
+
+
+def is_hangul_syllable(cp):
+    return 0xAC00 <= cp < 0xAC00 + 11172
+
+
+def canonical(cp):
+    d = unicodedata.decomposition(chr(cp))
+    if not d or d.startswith("<"):
+        return None
+    return [int(x, 16) for x in d.split()]
+
+
+def rows(entries, fmt):
+    out = []
+    for i in range(0, len(entries), 5):
+        out.append("    " + ", ".join(fmt(e) for e in entries[i:i + 5]) + ",")
+    return "\n".join(out)
+
+
+def hex4(cp):
+    return "0x%04X" % cp
+
+
+def main():
+    if unicodedata.unidata_version != UNICODE_VERSION:
+        sys.exit("unicodedata fournit Unicode %s, %s attendu" % (unicodedata.unidata_version, UNICODE_VERSION))
+    decompositions = []
+    compositions = []
+    for cp in range(0x110000):
+        if is_hangul_syllable(cp):
+            continue
+        d = canonical(cp)
+        if d is None:
+            continue
+        decompositions.append((cp, d[0], d[1] if len(d) > 1 else 0))
+        # Composée primaire : deux caractères et hors exclusions de composition, que NFC préserve
+        if len(d) == 2 and unicodedata.normalize("NFC", chr(cp)) == chr(cp):
+            compositions.append((d[0], d[1], cp))
+    compositions.sort()
+    classes = []
+    for cp in range(0x110000):
+        ccc = unicodedata.combining(chr(cp))
+        if ccc == 0:
+            continue
+        if classes and classes[-1][1] == cp - 1 and classes[-1][2] == ccc:
+            classes[-1][1] = cp
+        else:
+            classes.append([cp, cp, ccc])
+    triple = lambda e: "(%s, %s, %s)" % (hex4(e[0]), hex4(e[1]), hex4(e[2]))
+    print("static DECOMPOSITIONS: [(u32, u32, u32); %d] = [" % len(decompositions))
+    print(rows(decompositions, triple))
+    print("];\n")
+    print("static COMPOSITIONS: [(u32, u32, u32); %d] = [" % len(compositions))
+    print(rows(compositions, triple))
+    print("];\n")
+    print("static COMBINING_CLASSES: [(u32, u32, u8); %d] = [" % len(classes))
+    print(rows(classes, lambda e: "(%s, %s, %d)" % (hex4(e[0]), hex4(e[1]), e[2])))
+    print("];")
+
+
+main()
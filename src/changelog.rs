@@ -0,0 +1,126 @@
+use crate::json::{unescape, JsonValue};
+use crate::render::EntityNames;
+use crate::summary::{changed_fields, collection_label, entity_id, escape_markdown, index_move};
+
+// « Nom (id) » en Markdown, ou l'identifiant seul pour une entité sans nom
+fn entity_item(collection: &str, entity: &JsonValue, names: &EntityNames) -> String {
+    let id = entity_id(entity);
+    let own_name = entity.get("name").or_else(|| entity.get("label")).and_then(|v| v.as_str()).map(unescape);
+    match own_name.or_else(|| names.name(collection, &id)) {
+        Some(name) => format!("{} (`{}`)", escape_markdown(&name), id.replace('`', "'")),
+        None => format!("`{}`", id.replace('`', "'")),
+    }
+}
+
+pub fn render_changelog(diff: &JsonValue, names: &EntityNames) -> String {
+    let mut s = String::new();
+    let JsonValue::Object(collections) = diff else { return s };
+    for (collection, value) in collections {
+        let section = match *collection {
+            "unknown" => continue,
+            "properties" => {
+                let keys: Vec<&str> = ["added", "removed", "modified", "renamed"].iter()
+                    .filter_map(|part| match value.get(part) {
+                        Some(JsonValue::Object(entries)) => Some(entries.iter().map(|(k, _)| *k)),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect();
+                if keys.is_empty() {
+                    continue;
+                }
+                let mut section = String::new();
+                for key in keys {
+                    section.push_str(&format!("- `{}` changed\n", key.replace('`', "'")));
+                }
+                section
+            }
+            _ => {
+                let items = |name: &str| value.get(name).and_then(|v| v.as_array()).unwrap_or(&[]);
+                let (added, removed, modified, moved) = (items("added"), items("removed"), items("modified"), items("moved"));
+                let mut counts = Vec::new();
+                for (list, word) in [(added, "added"), (removed, "removed"), (modified, "modified"), (moved, "moved")] {
+                    if !list.is_empty() {
+                        counts.push(format!("{} {}", list.len(), word));
+                    }
+                }
+                if counts.is_empty() {
+                    continue;
+                }
+                let mut section = format!("{}.\n", counts.join(", "));
+                let mut subsection = |title: &str, lines: Vec<String>| {
+                    if !lines.is_empty() {
+                        section.push_str(&format!("\n### {}\n\n", title));
+                        for line in lines {
+                            section.push_str(&format!("- {}\n", line));
+                        }
+                    }
+                };
+                subsection("Added", added.iter().map(|e| entity_item(collection, e, names)).collect());
+                subsection("Removed", removed.iter().map(|e| entity_item(collection, e, names)).collect());
+                subsection("Modified", modified.iter().map(|e| {
+                    let fields: Vec<String> = changed_fields(e).iter().map(|f| escape_markdown(f)).collect();
+                    format!("{}: {}", entity_item(collection, e, names), fields.join(", "))
+                }).collect());
+                subsection("Moved", moved.iter().map(|m| {
+                    if let Some((from, to)) = index_move(m) {
+                        return format!("{}: from position {} to {}", entity_item(collection, m, names), from, to);
+                    }
+                    let parent = |key| match m.get(key).and_then(|v| v.as_str()) {
+                        Some(id) => format!("`{}`", unescape(id).replace('`', "'")),
+                        None => "the root".to_string(),
+                    };
+                    format!("{}: from {} to {}", entity_item(collection, m, names), parent("from"), parent("to"))
+                }).collect());
+                section
+            }
+        };
+        if !s.is_empty() {
+            s.push('\n');
+        }
+        s.push_str(&format!("## {}\n\n", escape_markdown(collection_label(collection))));
+        s.push_str(&section);
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::options::DiffOptions;
+
+    #[test]
+    fn test_render_changelog() {
+        let diff = parse_json(r#"{
+            "taxons":{"added":[{"id":"t9","name":"Ficus *carica*"},{"id":"t10","name":"Morus"}],"removed":[],
+                      "modified":[{"id":"t1","name":{"old":"A","new":"B"},"added":{"nameEN":"x"}}],
+                      "moved":[{"id":"t4","from":"t1","to":null,"descendants":[]},{"id":"t1","fromIndex":0,"toIndex":1}]},
+            "characters":{"added":[],"removed":[]},
+            "books":{"added":[],"removed":[{"id":"b2","label":"Flora"}]},
+            "properties":{"modified":{"id":{"old":"ds1","new":"ds2"}}}
+        }"#).unwrap();
+        let old = parse_json(r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t4","name":"Ruellia"}]}"#).unwrap();
+        let new = parse_json(r#"{"taxons":[{"id":"t1","name":"B"},{"id":"t4","name":"Ruellia"}]}"#).unwrap();
+        let opts = DiffOptions::hazo();
+        let names = EntityNames::new(&old, &new, &opts);
+        assert_eq!(render_changelog(&diff, &names), concat!(
+            "## Taxa\n\n",
+            "2 added, 1 modified, 2 moved.\n\n",
+            "### Added\n\n",
+            "- Ficus \\*carica\\* (`t9`)\n",
+            "- Morus (`t10`)\n\n",
+            "### Modified\n\n",
+            "- B (`t1`): name, nameEN\n\n",
+            "### Moved\n\n",
+            "- Ruellia (`t4`): from `t1` to the root\n",
+            "- B (`t1`): from position 0 to 1\n",
+            "\n## Books\n\n",
+            "1 removed.\n\n",
+            "### Removed\n\n",
+            "- Flora (`b2`)\n",
+            "\n## Dataset\n\n",
+            "- `id` changed\n",
+        ));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use crate::hazo::{Character, Dataset};
+use crate::json::{escape, unescape};
+
+pub struct DeltaFiles {
+    pub specs: String,
+    pub chars: String,
+    pub items: String,
+}
+
+impl DeltaFiles {
+    pub fn to_json(&self) -> String {
+        format!("{{\"specs\":{},\"chars\":{},\"items\":{}}}", escape(&self.specs), escape(&self.chars), escape(&self.items))
+    }
+}
+
+// Les chevrons délimitent les commentaires DELTA (les balises HTML sont donc retirées) et une
+// barre oblique suivie d'un blanc termine l'élément
+fn delta_text(raw: &str) -> String {
+    let text = unescape(raw);
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                for c in chars.by_ref() {
+                    if c == '>' {
+                        break;
+                    }
+                }
+            }
+            '>' => out.push(')'),
+            '/' if chars.peek().is_none_or(|n| n.is_whitespace()) => out.push('-'),
+            c if c.is_whitespace() => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+fn is_text_character(c: &Character) -> bool {
+    c.states.is_empty()
+}
+
+fn specs_file(ds: &Dataset) -> String {
+    let mut s = String::from("*SHOW ~ Dataset specifications.\n\n");
+    s.push_str(&format!("*NUMBER OF CHARACTERS {}\n", ds.characters.len()));
+    let max_states = ds.characters.iter().map(|c| c.states.len()).max().unwrap_or(0);
+    s.push_str(&format!("*MAXIMUM NUMBER OF STATES {}\n", max_states));
+    s.push_str(&format!("*MAXIMUM NUMBER OF ITEMS {}\n", ds.taxons.len()));
+    let types: Vec<String> = ds.characters.iter().enumerate()
+        .filter(|(_, c)| is_text_character(c))
+        .map(|(i, _)| format!("{},TE", i + 1))
+        .collect();
+    s.push_str(&format!("*CHARACTER TYPES {}\n", types.join(" ")));
+    let states: Vec<String> = ds.characters.iter().enumerate()
+        .filter(|(_, c)| !is_text_character(c))
+        .map(|(i, c)| format!("{},{}", i + 1, c.states.len()))
+        .collect();
+    s.push_str(&format!("*NUMBERS OF STATES {}\n", states.join(" ")));
+    s
+}
+
+fn chars_file(ds: &Dataset) -> String {
+    let mut s = String::from("*SHOW ~ Character list.\n\n*CHARACTER LIST\n");
+    for (i, c) in ds.characters.iter().enumerate() {
+        s.push_str(&format!("\n#{}. {} <{}>/\n", i + 1, delta_text(c.name), delta_text(c.id)));
+        for (j, state_id) in c.states.iter().enumerate() {
+            let name = ds.state(state_id).map_or(*state_id, |st| st.name);
+            s.push_str(&format!("    {}. {}/\n", j + 1, delta_text(name)));
+        }
+    }
+    s
+}
+
+// Numéros DELTA d'un caractère et de ses états, dans l'ordre du jeu de données
+fn description_attributes(ds: &Dataset, descriptor_id: &str, states_ids: &[&str]) -> Option<String> {
+    let index = ds.characters.iter().position(|c| c.id == descriptor_id)?;
+    let character = &ds.characters[index];
+    let numbers: Vec<String> = states_ids.iter()
+        .filter_map(|s| character.states.iter().position(|cs| cs == s))
+        .map(|n| (n + 1).to_string())
+        .collect();
+    if numbers.is_empty() {
+        return None;
+    }
+    Some(format!("{},{}", index + 1, numbers.join("/")))
+}
+
+fn items_file(ds: &Dataset, only: Option<&HashSet<String>>) -> String {
+    let mut s = String::from("*SHOW ~ Item descriptions.\n\n*ITEM DESCRIPTIONS\n");
+    for t in ds.taxons.iter().filter(|t| only.is_none_or(|ids| ids.contains(t.id))) {
+        s.push_str(&format!("\n# {} <{}>/\n", delta_text(t.name), delta_text(t.id)));
+        let attributes: Vec<String> = t.descriptions.iter()
+            .filter_map(|d| description_attributes(ds, d.descriptor_id, &d.states_ids))
+            .collect();
+        if !attributes.is_empty() {
+            s.push_str(&attributes.join(" "));
+            s.push('\n');
+        }
+    }
+    s
+}
+
+// Si `only` est fourni, seuls ces taxons figurent dans le fichier items
+pub fn dataset_to_delta(ds: &Dataset, only: Option<&HashSet<String>>) -> DeltaFiles {
+    DeltaFiles { specs: specs_file(ds), chars: chars_file(ds), items: items_file(ds, only) }
+}
+
+// Taxons dont l'item DELTA doit être régénéré. Toute modification de la numérotation des
+// caractères ou des états invalide l'ensemble des items.
+pub fn changed_items(old: &Dataset, new: &Dataset, changed_taxa: &HashSet<String>) -> HashSet<String> {
+    let numbering = |ds: &Dataset| -> Vec<(String, Vec<String>)> {
+        ds.characters.iter().map(|c| (c.id.to_string(), c.states.iter().map(|s| s.to_string()).collect())).collect()
+    };
+    if numbering(old) != numbering(new) {
+        return new.taxons.iter().map(|t| t.id.to_string()).collect();
+    }
+    new.taxons.iter().map(|t| t.id).filter(|id| changed_taxa.contains(*id)).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DATASET: &str = r#"{
+        "taxons": [
+            {"id": "t1", "name": "Acanthus <i>mollis</i>", "descriptions": [{"descriptorId": "c1", "statesIds": ["s2", "s1"]}, {"descriptorId": "c3", "statesIds": []}]},
+            {"id": "t2", "name": "Ficus", "descriptions": [{"descriptorId": "c2", "statesIds": ["s3"]}]}
+        ],
+        "characters": [
+            {"id": "c1", "name": "Color", "states": ["s1", "s2"]},
+            {"id": "c2", "name": "Size", "states": ["s3"]},
+            {"id": "c3", "name": "Notes", "states": []}
+        ],
+        "states": [{"id": "s1", "name": "Red"}, {"id": "s2", "name": "Blue/"}, {"id": "s3", "name": "Big"}]
+    }"#;
+
+    #[test]
+    fn test_dataset_to_delta() {
+        let value = parse_json(DATASET).unwrap();
+        let ds = Dataset::from_json(&value).unwrap();
+        let files = dataset_to_delta(&ds, None);
+        assert!(files.specs.contains("*NUMBER OF CHARACTERS 3\n*MAXIMUM NUMBER OF STATES 2\n*MAXIMUM NUMBER OF ITEMS 2\n"));
+        assert!(files.specs.contains("*CHARACTER TYPES 3,TE\n*NUMBERS OF STATES 1,2 2,1\n"));
+        assert!(files.chars.contains("#1. Color <c1>/\n    1. Red/\n    2. Blue-/\n"));
+        assert!(files.items.contains("# Acanthus mollis <t1>/\n1,2/1\n"));
+        assert!(files.items.contains("# Ficus <t2>/\n2,1\n"));
+
+        let only: HashSet<String> = ["t2".to_string()].into();
+        let files = dataset_to_delta(&ds, Some(&only));
+        assert!(!files.items.contains("<t1>"));
+        assert!(files.items.contains("<t2>"));
+    }
+
+    #[test]
+    fn test_changed_items() {
+        let old_value = parse_json(DATASET).unwrap();
+        let old = Dataset::from_json(&old_value).unwrap();
+        let changed: HashSet<String> = ["t1".to_string()].into();
+        assert_eq!(changed_items(&old, &old, &changed), changed);
+
+        let new_json = DATASET.replace(r#""states": ["s3"]"#, r#""states": ["s3", "s1"]"#);
+        let new_value = parse_json(&new_json).unwrap();
+        let new = Dataset::from_json(&new_value).unwrap();
+        assert_eq!(changed_items(&old, &new, &changed).len(), 2);
+    }
+}
@@ -5,10 +5,80 @@ pub enum JsonDiffErrorType {
     InvalidStructureUnclosed,
     InvalidStructureUnexpectedToken,
     InvalidStructureInvalidNumber,
+    InvalidOptions,
+    InvalidDataset,
+    InvalidXml,
+    TypeChanged,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum DiffInput {
+    Old,
+    New,
 }
 
 #[cfg_attr(test, derive(Debug))]
 pub struct JsonDiffError {
-    #[allow(dead_code)]
     pub error_type: JsonDiffErrorType,
-}
\ No newline at end of file
+    pub property: Option<String>,
+    pub input: Option<DiffInput>,
+    // Chemin de la valeur en cause, les entités désignées par leur identifiant
+    pub location: Option<String>,
+}
+
+impl JsonDiffError {
+    pub fn new(error_type: JsonDiffErrorType) -> Self {
+        JsonDiffError { error_type, property: None, input: None, location: None }
+    }
+
+    pub fn type_changed(location: String) -> Self {
+        JsonDiffError { location: Some(location), ..JsonDiffError::new(JsonDiffErrorType::TypeChanged) }
+    }
+
+    pub fn property_missing(property: &str, input: DiffInput) -> Self {
+        JsonDiffError {
+            error_type: JsonDiffErrorType::PropertyMissing,
+            property: Some(property.to_string()),
+            input: Some(input),
+            location: None,
+        }
+    }
+
+    // Attribue l'erreur à l'une des deux entrées si elle ne l'est pas déjà
+    pub fn in_input(mut self, input: DiffInput) -> Self {
+        self.input.get_or_insert(input);
+        self
+    }
+
+    // JSON ou XML mal formé
+    pub fn is_parse_error(&self) -> bool {
+        matches!(
+            self.error_type,
+            JsonDiffErrorType::InvalidStructureObjectKey
+                | JsonDiffErrorType::InvalidStructureUnclosed
+                | JsonDiffErrorType::InvalidStructureUnexpectedToken
+                | JsonDiffErrorType::InvalidStructureInvalidNumber
+                | JsonDiffErrorType::InvalidXml
+        )
+    }
+
+    pub fn is_property_missing(&self) -> bool {
+        matches!(self.error_type, JsonDiffErrorType::PropertyMissing)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum FieldErrorKind {
+    Missing,
+    ExpectedString,
+    ExpectedArray,
+    ExpectedObject,
+}
+
+#[cfg_attr(test, derive(Debug))]
+pub struct FieldError {
+    pub path: String,
+    pub kind: FieldErrorKind,
+}
@@ -0,0 +1,252 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::json::{unescape, JsonValue};
+use crate::jsondiff::canonical_json;
+use crate::options::FuzzyMatch;
+
+// Bigrammes de caractères triés avec leur nombre d'occurrences
+fn bigrams(text: &str) -> Vec<((char, char), usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut counts: HashMap<(char, char), usize> = HashMap::new();
+    for pair in chars.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_default() += 1;
+    }
+    let mut sorted: Vec<_> = counts.into_iter().collect();
+    sorted.sort_unstable();
+    sorted
+}
+
+// Valeur d'un champ prête à être comparée : bigrammes d'un texte, forme canonique sinon
+enum FieldProfile {
+    Text { text: String, bigrams: Vec<((char, char), usize)>, total: usize },
+    Other(String),
+}
+
+impl FieldProfile {
+    fn new(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::String(raw) => {
+                let text = unescape(raw).into_owned();
+                let bigrams = bigrams(&text);
+                let total = bigrams.iter().map(|(_, n)| n).sum();
+                FieldProfile::Text { text, bigrams, total }
+            }
+            other => FieldProfile::Other(canonical_json(other)),
+        }
+    }
+
+    // Coefficient de Dice sur les bigrammes de caractères : linéaire, contrairement à un diff
+    // de séquences, ce qui compte quand on compare de nombreuses paires d'entités
+    fn similarity(&self, other: &FieldProfile) -> f64 {
+        match (self, other) {
+            (FieldProfile::Text { text: ta, bigrams: ba, total: na }, FieldProfile::Text { text: tb, bigrams: bb, total: nb }) => {
+                if ta == tb {
+                    return 1.0;
+                }
+                if na + nb == 0 {
+                    return 0.0;
+                }
+                let (mut i, mut j, mut common) = (0, 0, 0);
+                while i < ba.len() && j < bb.len() {
+                    match ba[i].0.cmp(&bb[j].0) {
+                        Ordering::Less => i += 1,
+                        Ordering::Greater => j += 1,
+                        Ordering::Equal => {
+                            common += ba[i].1.min(bb[j].1);
+                            i += 1;
+                            j += 1;
+                        }
+                    }
+                }
+                2.0 * common as f64 / (na + nb) as f64
+            }
+            (FieldProfile::Other(a), FieldProfile::Other(b)) if a == b => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    // Majorant de `similarity` tiré des seules longueurs
+    fn similarity_bound(&self, other: &FieldProfile) -> f64 {
+        match (self, other) {
+            (FieldProfile::Text { total: na, .. }, FieldProfile::Text { total: nb, .. }) if na + nb > 0 => {
+                (2 * na.min(nb)) as f64 / (na + nb) as f64
+            }
+            (FieldProfile::Text { .. }, FieldProfile::Text { .. }) => 1.0,
+            _ => self.similarity(other),
+        }
+    }
+}
+
+// Champs d'une entité hors clé d'identité, triés, calculés une fois pour toutes les paires
+struct EntityProfile<'a> {
+    fields: Vec<(&'a str, FieldProfile)>,
+    // Nom en minuscules aux blancs réduits, pour le blocage des candidats
+    name: Option<String>,
+}
+
+impl<'a> EntityProfile<'a> {
+    fn new(entity: &JsonValue<'a>, key: &str) -> Self {
+        let JsonValue::Object(fields) = entity else { return EntityProfile { fields: Vec::new(), name: None } };
+        let mut profiled: Vec<(&'a str, FieldProfile)> = fields.iter()
+            .filter(|(k, _)| *k != key)
+            .map(|(k, v)| (*k, FieldProfile::new(v)))
+            .collect();
+        profiled.sort_by(|a, b| a.0.cmp(b.0));
+        profiled.dedup_by(|a, b| a.0 == b.0);
+        let name = profiled.iter().find(|(k, _)| *k == "name").and_then(|(_, v)| match v {
+            FieldProfile::Text { text, .. } => Some(text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()),
+            FieldProfile::Other(_) => None,
+        }).filter(|name| !name.is_empty());
+        EntityProfile { fields: profiled, name }
+    }
+
+    // Moyenne pondérée de `field_score` sur l'union des champs, un champ absent d'un côté comptant 0
+    fn score(&self, other: &EntityProfile, fuzzy: &FuzzyMatch, field_score: fn(&FieldProfile, &FieldProfile) -> f64) -> f64 {
+        let (fa, fb) = (&self.fields, &other.fields);
+        let (mut i, mut j) = (0, 0);
+        let (mut score, mut total) = (0.0, 0.0);
+        while i < fa.len() || j < fb.len() {
+            let order = match (fa.get(i), fb.get(j)) {
+                (Some(a), Some(b)) => a.0.cmp(b.0),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            match order {
+                Ordering::Less => {
+                    total += fuzzy.weight(fa[i].0);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    total += fuzzy.weight(fb[j].0);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    let weight = fuzzy.weight(fa[i].0);
+                    score += weight * field_score(&fa[i].1, &fb[j].1);
+                    total += weight;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        if total == 0.0 { 0.0 } else { score / total }
+    }
+
+    // Similarité pondérée entre deux entités, de 0 à 1, sans tenir compte de leur clé d'identité
+    fn similarity(&self, other: &EntityProfile, fuzzy: &FuzzyMatch) -> f64 {
+        self.score(other, fuzzy, FieldProfile::similarity)
+    }
+}
+
+// Retient les candidats par similarité décroissante ; chaque entité appartient au plus à une paire
+fn select(mut candidates: Vec<(f64, usize, usize)>, used_old: &mut [bool], used_new: &mut [bool], pairs: &mut Vec<(usize, usize)>) {
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+    for (_, i, j) in candidates {
+        if !used_old[i] && !used_new[j] {
+            used_old[i] = true;
+            used_new[j] = true;
+            pairs.push((i, j));
+        }
+    }
+}
+
+// Paires (ancien, nouveau) d'entités supprimées et ajoutées assez semblables. Les entités de
+// même nom sont appariées d'abord entre elles ; les autres paires ne sont évaluées que si le
+// majorant tiré de la longueur des champs atteint le seuil.
+pub fn match_entities(va: &[JsonValue], vb: &[JsonValue], removed: &[usize], added: &[usize], key: &str, fuzzy: &FuzzyMatch) -> Vec<(usize, usize)> {
+    let old: Vec<(usize, EntityProfile)> = removed.iter().map(|&i| (i, EntityProfile::new(&va[i], key))).collect();
+    let new: Vec<(usize, EntityProfile)> = added.iter().map(|&j| (j, EntityProfile::new(&vb[j], key))).collect();
+    let (mut used_old, mut used_new) = (vec![false; va.len()], vec![false; vb.len()]);
+    let mut pairs = Vec::new();
+
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (pos, (_, p)) in new.iter().enumerate() {
+        if let Some(name) = &p.name {
+            by_name.entry(name.as_str()).or_default().push(pos);
+        }
+    }
+    let mut candidates = Vec::new();
+    for (i, a) in &old {
+        for &pos in a.name.as_deref().and_then(|name| by_name.get(name)).into_iter().flatten() {
+            let (j, b) = &new[pos];
+            let s = a.similarity(b, fuzzy);
+            if s >= fuzzy.threshold {
+                candidates.push((s, *i, *j));
+            }
+        }
+    }
+    select(candidates, &mut used_old, &mut used_new, &mut pairs);
+
+    let mut candidates = Vec::new();
+    for (i, a) in old.iter().filter(|(i, _)| !used_old[*i]) {
+        for (j, b) in new.iter().filter(|(j, _)| !used_new[*j]) {
+            if a.score(b, fuzzy, FieldProfile::similarity_bound) < fuzzy.threshold {
+                continue;
+            }
+            let s = a.similarity(b, fuzzy);
+            if s >= fuzzy.threshold {
+                candidates.push((s, *i, *j));
+            }
+        }
+    }
+    select(candidates, &mut used_old, &mut used_new, &mut pairs);
+    pairs.sort_unstable_by_key(|&(_, j)| j);
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    fn similarity(a: &JsonValue, b: &JsonValue, fuzzy: &FuzzyMatch) -> f64 {
+        EntityProfile::new(a, "id").similarity(&EntityProfile::new(b, "id"), fuzzy)
+    }
+
+    #[test]
+    fn test_similarity() {
+        let fuzzy = FuzzyMatch { threshold: 0.8, weights: vec![("name".to_string(), 3.0)] };
+        let a = parse_json(r#"{"id":"t1","name":"Ficus carica","detail":"Fig tree"}"#).unwrap();
+        let b = parse_json(r#"{"id":"x9","name":"Ficus carica","detail":"Common fig"}"#).unwrap();
+        let s = similarity(&a, &b, &fuzzy);
+        assert!(s > 0.75 && s < 1.0, "{}", s);
+        assert_eq!(similarity(&a, &a, &fuzzy), 1.0);
+        let text = |t: &str| FieldProfile::new(&JsonValue::String(t));
+        assert_eq!(text("night").similarity(&text("nacht")), 0.25);
+        assert!(text("night").similarity_bound(&text("nacht")) >= 0.25);
+    }
+
+    #[test]
+    fn test_match_entities() {
+        let fuzzy = FuzzyMatch::new(0.6);
+        let va = parse_json(r#"[{"id":"a","name":"Morus alba"},{"id":"b","name":"Ficus carica"}]"#).unwrap();
+        let vb = parse_json(r#"[{"id":"y","name":"Ficus carica L."},{"id":"z","name":"Quercus robur"}]"#).unwrap();
+        let (va, vb) = (va.as_array().unwrap(), vb.as_array().unwrap());
+        assert_eq!(match_entities(va, vb, &[0, 1], &[0, 1], "id", &fuzzy), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_match_entities_by_name_first() {
+        // Le nom identique l'emporte sur un détail plus proche
+        let fuzzy = FuzzyMatch::new(0.4);
+        let va = parse_json(r#"[{"id":"a","name":"Ficus  Carica","detail":"Fig"}]"#).unwrap();
+        let vb = parse_json(r#"[{"id":"y","name":"Ficus caricas","detail":"Fig"},{"id":"z","name":"ficus carica","detail":"Common fig"}]"#).unwrap();
+        let (va, vb) = (va.as_array().unwrap(), vb.as_array().unwrap());
+        assert_eq!(match_entities(va, vb, &[0], &[0, 1], "id", &fuzzy), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_match_entities_many() {
+        let fuzzy = FuzzyMatch::new(0.8);
+        let old: Vec<String> = (0..2000).map(|i| format!(r#"{{"id":"o{}","name":"Species number {}","detail":"Described in {}"}}"#, i, i, 1800 + i)).collect();
+        let new: Vec<String> = (0..2000).map(|i| format!(r#"{{"id":"n{}","name":"Species number {}","detail":"Described in {}."}}"#, i, i, 1800 + i)).collect();
+        let (old, new) = (format!("[{}]", old.join(",")), format!("[{}]", new.join(",")));
+        let (va, vb) = (parse_json(&old).unwrap(), parse_json(&new).unwrap());
+        let (va, vb) = (va.as_array().unwrap(), vb.as_array().unwrap());
+        let all: Vec<usize> = (0..2000).collect();
+        let pairs = match_entities(va, vb, &all, &all, "id", &fuzzy);
+        assert_eq!(pairs.len(), 2000);
+        assert!(pairs.iter().all(|(i, j)| i == j));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::errors::{FieldError, FieldErrorKind};
+use crate::json::JsonValue;
+
+pub type Fields<'a> = Vec<(&'a str, JsonValue<'a>)>;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Description<'a> {
+    pub descriptor_id: &'a str,
+    pub states_ids: Vec<&'a str>,
+    pub extra: Fields<'a>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Taxon<'a> {
+    pub id: &'a str,
+    pub path: Vec<&'a str>,
+    pub name: &'a str,
+    pub name_en: &'a str,
+    pub name_cn: &'a str,
+    pub vernacular_name: &'a str,
+    pub detail: &'a str,
+    pub children: Vec<&'a str>,
+    pub descriptions: Vec<Description<'a>>,
+    pub extra: Fields<'a>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Character<'a> {
+    pub id: &'a str,
+    pub path: Vec<&'a str>,
+    pub name: &'a str,
+    pub name_en: &'a str,
+    pub name_cn: &'a str,
+    pub detail: &'a str,
+    pub children: Vec<&'a str>,
+    pub states: Vec<&'a str>,
+    pub inherent_state_id: Option<&'a str>,
+    pub inapplicable_states_ids: Vec<&'a str>,
+    pub required_states_ids: Vec<&'a str>,
+    pub extra: Fields<'a>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct State<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub name_en: &'a str,
+    pub name_cn: &'a str,
+    pub description: &'a str,
+    pub extra: Fields<'a>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Book<'a> {
+    pub id: &'a str,
+    pub label: &'a str,
+    pub extra: Fields<'a>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Dataset<'a> {
+    pub id: Option<&'a str>,
+    pub taxons: Vec<Taxon<'a>>,
+    pub characters: Vec<Character<'a>>,
+    pub states: Vec<State<'a>>,
+    pub books: Vec<Book<'a>>,
+    pub extra: Fields<'a>,
+}
+
+// Lecture des champs d'un objet JSON, en gardant trace des clés consommées
+// pour conserver les champs inconnus et des erreurs rencontrées.
+struct FieldReader<'v, 'a> {
+    fields: &'v [(&'a str, JsonValue<'a>)],
+    used: Vec<bool>,
+    path: String,
+    errors: &'v mut Vec<FieldError>,
+}
+
+impl<'v, 'a> FieldReader<'v, 'a> {
+    fn new(value: &'v JsonValue<'a>, path: String, errors: &'v mut Vec<FieldError>) -> Option<Self> {
+        match value {
+            JsonValue::Object(fields) => Some(FieldReader {
+                fields,
+                used: vec![false; fields.len()],
+                path,
+                errors,
+            }),
+            _ => {
+                errors.push(FieldError { path, kind: FieldErrorKind::ExpectedObject });
+                None
+            }
+        }
+    }
+
+    fn error(&mut self, key: &str, kind: FieldErrorKind) {
+        self.errors.push(FieldError { path: format!("{}/{}", self.path, key), kind });
+    }
+
+    fn get(&mut self, key: &str) -> Option<&'v JsonValue<'a>> {
+        let i = self.fields.iter().position(|(k, _)| *k == key)?;
+        self.used[i] = true;
+        match &self.fields[i].1 {
+            JsonValue::Null => None,
+            v => Some(v),
+        }
+    }
+
+    fn required_str(&mut self, key: &str) -> &'a str {
+        match self.get(key) {
+            Some(JsonValue::String(s)) => s,
+            Some(_) => {
+                self.error(key, FieldErrorKind::ExpectedString);
+                ""
+            }
+            None => {
+                self.error(key, FieldErrorKind::Missing);
+                ""
+            }
+        }
+    }
+
+    fn optional_str(&mut self, key: &str) -> Option<&'a str> {
+        match self.get(key) {
+            Some(JsonValue::String(s)) => Some(s),
+            Some(_) => {
+                self.error(key, FieldErrorKind::ExpectedString);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn str_or_empty(&mut self, key: &str) -> &'a str {
+        self.optional_str(key).unwrap_or("")
+    }
+
+    fn array(&mut self, key: &str) -> &'v [JsonValue<'a>] {
+        match self.get(key) {
+            Some(JsonValue::Array(items)) => items,
+            Some(_) => {
+                self.error(key, FieldErrorKind::ExpectedArray);
+                &[]
+            }
+            None => &[],
+        }
+    }
+
+    fn str_list(&mut self, key: &str) -> Vec<&'a str> {
+        let items = self.array(key);
+        let mut list = Vec::with_capacity(items.len());
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                JsonValue::String(s) => list.push(*s),
+                _ => self.error(&format!("{}/{}", key, i), FieldErrorKind::ExpectedString),
+            }
+        }
+        list
+    }
+
+    fn objects<T>(&mut self, key: &str, parse: fn(&JsonValue<'a>, String, &mut Vec<FieldError>) -> Option<T>) -> Vec<T> {
+        let items = self.array(key);
+        let mut list = Vec::with_capacity(items.len());
+        for (i, item) in items.iter().enumerate() {
+            let path = format!("{}/{}/{}", self.path, key, i);
+            if let Some(v) = parse(item, path, self.errors) {
+                list.push(v);
+            }
+        }
+        list
+    }
+
+    fn extra(self) -> Fields<'a> {
+        self.fields.iter().zip(self.used).filter(|(_, used)| !used).map(|(f, _)| f.clone()).collect()
+    }
+}
+
+impl<'a> Description<'a> {
+    fn parse(value: &JsonValue<'a>, path: String, errors: &mut Vec<FieldError>) -> Option<Self> {
+        let mut r = FieldReader::new(value, path, errors)?;
+        Some(Description {
+            descriptor_id: r.required_str("descriptorId"),
+            states_ids: r.str_list("statesIds"),
+            extra: r.extra(),
+        })
+    }
+}
+
+impl<'a> Taxon<'a> {
+    pub fn from_json(value: &JsonValue<'a>) -> Result<Self, Vec<FieldError>> {
+        collect_errors(|errors| Taxon::parse(value, String::new(), errors))
+    }
+
+    fn parse(value: &JsonValue<'a>, path: String, errors: &mut Vec<FieldError>) -> Option<Self> {
+        let mut r = FieldReader::new(value, path, errors)?;
+        Some(Taxon {
+            id: r.required_str("id"),
+            path: r.str_list("path"),
+            name: r.required_str("name"),
+            name_en: r.str_or_empty("nameEN"),
+            name_cn: r.str_or_empty("nameCN"),
+            vernacular_name: r.str_or_empty("vernacularName"),
+            detail: r.str_or_empty("detail"),
+            children: r.str_list("children"),
+            descriptions: r.objects("descriptions", Description::parse),
+            extra: r.extra(),
+        })
+    }
+}
+
+impl<'a> Character<'a> {
+    pub fn from_json(value: &JsonValue<'a>) -> Result<Self, Vec<FieldError>> {
+        collect_errors(|errors| Character::parse(value, String::new(), errors))
+    }
+
+    fn parse(value: &JsonValue<'a>, path: String, errors: &mut Vec<FieldError>) -> Option<Self> {
+        let mut r = FieldReader::new(value, path, errors)?;
+        Some(Character {
+            id: r.required_str("id"),
+            path: r.str_list("path"),
+            name: r.required_str("name"),
+            name_en: r.str_or_empty("nameEN"),
+            name_cn: r.str_or_empty("nameCN"),
+            detail: r.str_or_empty("detail"),
+            children: r.str_list("children"),
+            states: r.str_list("states"),
+            inherent_state_id: r.optional_str("inherentStateId"),
+            inapplicable_states_ids: r.str_list("inapplicableStatesIds"),
+            required_states_ids: r.str_list("requiredStatesIds"),
+            extra: r.extra(),
+        })
+    }
+}
+
+impl<'a> State<'a> {
+    pub fn from_json(value: &JsonValue<'a>) -> Result<Self, Vec<FieldError>> {
+        collect_errors(|errors| State::parse(value, String::new(), errors))
+    }
+
+    fn parse(value: &JsonValue<'a>, path: String, errors: &mut Vec<FieldError>) -> Option<Self> {
+        let mut r = FieldReader::new(value, path, errors)?;
+        Some(State {
+            id: r.required_str("id"),
+            name: r.required_str("name"),
+            name_en: r.str_or_empty("nameEN"),
+            name_cn: r.str_or_empty("nameCN"),
+            description: r.str_or_empty("description"),
+            extra: r.extra(),
+        })
+    }
+}
+
+impl<'a> Book<'a> {
+    pub fn from_json(value: &JsonValue<'a>) -> Result<Self, Vec<FieldError>> {
+        collect_errors(|errors| Book::parse(value, String::new(), errors))
+    }
+
+    fn parse(value: &JsonValue<'a>, path: String, errors: &mut Vec<FieldError>) -> Option<Self> {
+        let mut r = FieldReader::new(value, path, errors)?;
+        Some(Book {
+            id: r.required_str("id"),
+            label: r.str_or_empty("label"),
+            extra: r.extra(),
+        })
+    }
+}
+
+impl<'a> Dataset<'a> {
+    pub fn from_json(value: &JsonValue<'a>) -> Result<Self, Vec<FieldError>> {
+        collect_errors(|errors| {
+            let mut r = FieldReader::new(value, String::new(), errors)?;
+            Some(Dataset {
+                id: r.optional_str("id"),
+                taxons: r.objects("taxons", Taxon::parse),
+                characters: r.objects("characters", Character::parse),
+                states: r.objects("states", State::parse),
+                books: r.objects("books", Book::parse),
+                extra: r.extra(),
+            })
+        })
+    }
+
+    pub fn taxon(&self, id: &str) -> Option<&Taxon<'a>> {
+        self.taxons.iter().find(|t| t.id == id)
+    }
+
+    pub fn character(&self, id: &str) -> Option<&Character<'a>> {
+        self.characters.iter().find(|c| c.id == id)
+    }
+
+    pub fn state(&self, id: &str) -> Option<&State<'a>> {
+        self.states.iter().find(|s| s.id == id)
+    }
+
+    pub fn book(&self, id: &str) -> Option<&Book<'a>> {
+        self.books.iter().find(|b| b.id == id)
+    }
+}
+
+fn collect_errors<T>(parse: impl FnOnce(&mut Vec<FieldError>) -> Option<T>) -> Result<T, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    match parse(&mut errors) {
+        Some(v) if errors.is_empty() => Ok(v),
+        _ => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn test_parse_taxon() {
+        let input = r#"{
+            "id": "t1", "path": ["t0"], "name": "Acanthaceae", "nameEN": "", "nameCN": "爵床科",
+            "vernacularName": "", "detail": "", "children": ["t2"],
+            "descriptions": [{"descriptorId": "c1", "statesIds": ["s1", "s2"]}]
+        }"#;
+        let v = parse_json(input).unwrap();
+        let taxon = Taxon::from_json(&v).unwrap();
+        assert_eq!(taxon.id, "t1");
+        assert_eq!(taxon.path, vec!["t0"]);
+        assert_eq!(taxon.name_cn, "爵床科");
+        assert_eq!(taxon.children, vec!["t2"]);
+        assert_eq!(taxon.descriptions[0].descriptor_id, "c1");
+        assert_eq!(taxon.descriptions[0].states_ids, vec!["s1", "s2"]);
+        assert!(taxon.extra.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_fields_preserved() {
+        let v = parse_json(r#"{"id":"s1","name":"Red","color":"red","photos":[]}"#).unwrap();
+        let state = State::from_json(&v).unwrap();
+        assert_eq!(state.extra.len(), 2);
+        assert_eq!(state.extra[0].0, "color");
+        assert_eq!(state.extra[0].1, JsonValue::String("red"));
+        assert_eq!(state.extra[1].0, "photos");
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let v = parse_json(r#"{"id":3,"path":["t0",1],"children":"t2"}"#).unwrap();
+        let errors = Taxon::from_json(&v).unwrap_err();
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, vec![
+            "/id: expected a string",
+            "/path/1: expected a string",
+            "/name: missing field",
+            "/children: expected an array",
+        ]);
+    }
+
+    #[test]
+    fn test_parse_dataset() {
+        let input = r#"{
+            "id": "ds",
+            "taxons": [{"id": "t1", "name": "A"}, {"id": "t2"}],
+            "characters": [{"id": "c1", "name": "Color", "states": ["s1"], "inherentStateId": null}],
+            "states": [{"id": "s1", "name": "Red"}],
+            "extraFields": []
+        }"#;
+        let v = parse_json(input).unwrap();
+        let errors = Dataset::from_json(&v).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "/taxons/1/name");
+        assert_eq!(errors[0].kind, FieldErrorKind::Missing);
+
+        let input = input.replace(r#"{"id": "t2"}"#, r#"{"id": "t2", "name": "B"}"#);
+        let v = parse_json(&input).unwrap();
+        let ds = Dataset::from_json(&v).unwrap();
+        assert_eq!(ds.id, Some("ds"));
+        assert_eq!(ds.taxon("t2").unwrap().name, "B");
+        assert_eq!(ds.character("c1").unwrap().inherent_state_id, None);
+        assert_eq!(ds.state("s1").unwrap().name, "Red");
+        assert!(ds.books.is_empty());
+        assert_eq!(ds.extra[0].0, "extraFields");
+    }
+}
@@ -0,0 +1,262 @@
+use std::borrow::Cow;
+
+use crate::json::{unescape, JsonValue};
+use crate::render::{change_to_text, display_value, entity_title, move_text, EntityNames};
+use crate::seqdiff::{myers_diff, split_words, EditKind};
+use crate::summary::{collection_label, is_scalar_change};
+use crate::xml::xml_escape;
+
+// Au-delà de cette longueur, les textes modifiés sont comparés mot à mot
+const WORD_DIFF_MIN_LEN: usize = 80;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+h1{font-size:1.5em}\
+details{margin:.3em 0}\
+details.collection>summary{font-size:1.2em;font-weight:bold;cursor:pointer}\
+details.entity{margin-left:1.5em;border-left:3px solid #ccc;padding-left:.5em}\
+details.entity>summary{cursor:pointer}\
+details.added{border-color:#2a2}\
+details.removed{border-color:#c33}\
+details.modified{border-color:#d90}\
+.count{color:#777;font-weight:normal;font-size:.8em}\
+p.moved{margin-left:1.5em;color:#555}\
+table{border-collapse:collapse;margin:.5em 0;width:100%;table-layout:fixed}\
+th,td{border:1px solid #ddd;padding:.3em .5em;text-align:left;vertical-align:top;white-space:pre-wrap;word-wrap:break-word}\
+th.field,td.field{width:12em}\
+td.old{background:#fee}\
+td.new{background:#efe}\
+del{background:#f99;text-decoration:line-through}\
+ins{background:#9e9;text-decoration:none}";
+
+fn text_of(v: &JsonValue) -> Cow<'static, str> {
+    match v {
+        JsonValue::String(s) => Cow::Owned(unescape(s).into_owned()),
+        other => Cow::Owned(display_value(other)),
+    }
+}
+
+// Mise en évidence mot à mot : suppressions dans l'ancienne valeur, insertions dans la nouvelle.
+// Un blanc isolé entre deux modifications est rattaché à celles-ci pour ne pas morceler le surlignage.
+fn word_diff_cells(old: &str, new: &str) -> (String, String) {
+    let (a, b) = (split_words(old), split_words(new));
+    let edits = myers_diff(&a, &b);
+    let (mut old_html, mut new_html) = (String::new(), String::new());
+    let (mut deleted, mut inserted) = (String::new(), String::new());
+    let flush = |old_html: &mut String, new_html: &mut String, deleted: &mut String, inserted: &mut String| {
+        if !deleted.is_empty() {
+            old_html.push_str(&format!("<del>{}</del>", xml_escape(deleted)));
+            deleted.clear();
+        }
+        if !inserted.is_empty() {
+            new_html.push_str(&format!("<ins>{}</ins>", xml_escape(inserted)));
+            inserted.clear();
+        }
+    };
+    for (i, e) in edits.iter().enumerate() {
+        match e.kind {
+            EditKind::Delete => deleted.push_str(&a[e.old.clone()].concat()),
+            EditKind::Insert => inserted.push_str(&b[e.new.clone()].concat()),
+            EditKind::Equal => {
+                let text = a[e.old.clone()].concat();
+                let pending = !deleted.is_empty() || !inserted.is_empty();
+                if pending && i + 1 < edits.len() && text.trim().is_empty() {
+                    deleted.push_str(&text);
+                    inserted.push_str(&text);
+                    continue;
+                }
+                flush(&mut old_html, &mut new_html, &mut deleted, &mut inserted);
+                let text = xml_escape(&text);
+                old_html.push_str(&text);
+                new_html.push_str(&text);
+            }
+        }
+    }
+    flush(&mut old_html, &mut new_html, &mut deleted, &mut inserted);
+    (old_html, new_html)
+}
+
+fn row(out: &mut String, field: &str, old: &str, new: &str) {
+    out.push_str(&format!("<tr><td class=\"field\">{}</td><td class=\"old\">{}</td><td class=\"new\">{}</td></tr>\n", xml_escape(field), old, new));
+}
+
+fn change_row(out: &mut String, field: &str, change: &JsonValue) {
+    if !is_scalar_change(change) {
+        let text = xml_escape(&change_to_text(change));
+        out.push_str(&format!("<tr><td class=\"field\">{}</td><td colspan=\"2\">{}</td></tr>\n", xml_escape(field), text.trim_end()));
+        return;
+    }
+    let (old, new) = (change.get("old").unwrap(), change.get("new").unwrap());
+    if let (Some(old_type), Some(new_type)) = (change.get("oldType"), change.get("newType")) {
+        let field = format!("{} ({} → {})", field, text_of(old_type), text_of(new_type));
+        row(out, &field, &xml_escape(&text_of(old)), &xml_escape(&text_of(new)));
+        return;
+    }
+    match (old, new) {
+        (JsonValue::String(a), JsonValue::String(b)) if a.len().max(b.len()) >= WORD_DIFF_MIN_LEN => {
+            let (old_html, new_html) = word_diff_cells(&unescape(a), &unescape(b));
+            row(out, field, &old_html, &new_html);
+        }
+        _ => row(out, field, &xml_escape(&text_of(old)), &xml_escape(&text_of(new))),
+    }
+}
+
+fn table_start(out: &mut String) {
+    out.push_str("<table>\n<tr><th class=\"field\">Field</th><th>Old</th><th>New</th></tr>\n");
+}
+
+fn value_rows(out: &mut String, values: &[(&str, JsonValue)], is_new: bool) {
+    for (k, v) in values {
+        let value = xml_escape(&text_of(v));
+        if is_new {
+            row(out, k, "", &value);
+        } else {
+            row(out, k, &value, "");
+        }
+    }
+}
+
+fn rename_rows(out: &mut String, renamed: &[(&str, JsonValue)]) {
+    for (old, new) in renamed {
+        out.push_str(&format!("<tr><td class=\"field\">{}</td><td colspan=\"2\">renamed to {}</td></tr>\n", xml_escape(old), xml_escape(&text_of(new))));
+    }
+}
+
+// Champs d'un diff à plat, comme pour le rendu texte
+fn field_rows(out: &mut String, diff: &JsonValue, entity: bool) {
+    let JsonValue::Object(fields) = diff else { return };
+    for (field, change) in fields {
+        match (*field, change) {
+            ("id", _) if entity => {}
+            ("index", _) if entity && diff.get("id").is_none() => {}
+            ("replaces", old_id) if entity => {
+                let new_id = diff.get("id").map_or(Cow::Borrowed(""), text_of);
+                row(out, "id", &xml_escape(&text_of(old_id)), &xml_escape(&new_id));
+            }
+            ("added", JsonValue::Object(added)) => value_rows(out, added, true),
+            ("removed", JsonValue::Object(removed)) => value_rows(out, removed, false),
+            ("renamed", JsonValue::Object(renamed)) => rename_rows(out, renamed),
+            _ => change_row(out, field, change),
+        }
+    }
+}
+
+fn entity_section(out: &mut String, class: &str, sign: &str, title: &str, entity: &JsonValue) {
+    out.push_str(&format!("<details class=\"entity {}\"><summary>{} {}</summary>\n", class, sign, xml_escape(title)));
+    table_start(out);
+    if let JsonValue::Object(fields) = entity {
+        value_rows(out, fields, class == "added");
+    }
+    out.push_str("</table>\n</details>\n");
+}
+
+fn count_label(n: usize) -> String {
+    if n == 1 { "1 change".to_string() } else { format!("{} changes", n) }
+}
+
+pub fn render_html(diff: &JsonValue, names: &EntityNames, title: &str) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", xml_escape(title), STYLE));
+    out.push_str(&format!("<h1>{}</h1>\n", xml_escape(title)));
+    let collections = match diff {
+        JsonValue::Object(collections) => collections.as_slice(),
+        _ => &[],
+    };
+    if collections.is_empty() {
+        out.push_str("<p>No differences.</p>\n");
+    }
+    for (collection, value) in collections {
+        match *collection {
+            "unknown" => {
+                let keys: Vec<String> = value.as_array().unwrap_or(&[]).iter().map(|k| xml_escape(&text_of(k))).collect();
+                out.push_str(&format!("<p>Unknown root keys: {}</p>\n", keys.join(", ")));
+            }
+            "properties" => {
+                out.push_str(&format!("<details class=\"collection\" open><summary>{}</summary>\n", collection_label(collection)));
+                table_start(&mut out);
+                if let Some(JsonValue::Object(added)) = value.get("added") {
+                    value_rows(&mut out, added, true);
+                }
+                if let Some(JsonValue::Object(removed)) = value.get("removed") {
+                    value_rows(&mut out, removed, false);
+                }
+                if let Some(modified) = value.get("modified") {
+                    field_rows(&mut out, modified, false);
+                }
+                if let Some(JsonValue::Object(renamed)) = value.get("renamed") {
+                    rename_rows(&mut out, renamed);
+                }
+                out.push_str("</table>\n</details>\n");
+            }
+            _ => {
+                let items = |name: &str| value.get(name).and_then(|v| v.as_array()).unwrap_or(&[]);
+                let count = items("added").len() + items("removed").len() + items("moved").len() + items("modified").len();
+                if count == 0 {
+                    continue;
+                }
+                out.push_str(&format!(
+                    "<details class=\"collection\" open><summary>{} <span class=\"count\">{}</span></summary>\n",
+                    xml_escape(collection_label(collection)),
+                    count_label(count),
+                ));
+                for entity in items("added") {
+                    entity_section(&mut out, "added", "+", &entity_title(collection, entity, names), entity);
+                }
+                for entity in items("removed") {
+                    entity_section(&mut out, "removed", "-", &entity_title(collection, entity, names), entity);
+                }
+                for m in items("moved") {
+                    let text = format!("{} moved {}", entity_title(collection, m, names), move_text(m));
+                    out.push_str(&format!("<p class=\"moved\">&gt; {}</p>\n", xml_escape(&text)));
+                }
+                for entry in items("modified") {
+                    let title = entity_title(collection, entry, names);
+                    out.push_str(&format!("<details class=\"entity modified\" open><summary>~ {}</summary>\n", xml_escape(&title)));
+                    table_start(&mut out);
+                    field_rows(&mut out, entry, true);
+                    out.push_str("</table>\n</details>\n");
+                }
+                out.push_str("</details>\n");
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn test_word_diff_cells() {
+        let (old, new) = word_diff_cells("Herbs or shrubs <1 m", "Herbs, rarely shrubs <1 m");
+        assert_eq!(old, "<del>Herbs or</del> shrubs &lt;1 m");
+        assert_eq!(new, "<ins>Herbs, rarely</ins> shrubs &lt;1 m");
+    }
+
+    #[test]
+    fn test_render_html() {
+        let long_old = "Perennial herbs with opposite leaves and showy flowers arranged in dense terminal spikes";
+        let long_new = "Perennial herbs with opposite leaves and large flowers arranged in dense terminal spikes";
+        let diff = format!(
+            r#"{{"taxons":{{"added":[{{"id":"t9","name":"Ficus & co"}}],"removed":[],"modified":[{{"id":"t1","detail":{{"old":"{}","new":"{}"}},"children":{{"added":["t5"],"removed":[]}}}}]}},"books":{{"added":[],"removed":[]}}}}"#,
+            long_old, long_new,
+        );
+        let diff = parse_json(&diff).unwrap();
+        let html = render_html(&diff, &EntityNames::empty(), "Dataset changes");
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(html.contains("<summary>Taxa <span class=\"count\">2 changes</span></summary>"));
+        assert!(html.contains("<summary>+ Taxon t9 (Ficus &amp; co)</summary>"));
+        assert!(html.contains("<td class=\"field\">name</td><td class=\"old\"></td><td class=\"new\">Ficus &amp; co</td>"));
+        assert!(html.contains("and <del>showy</del> flowers"));
+        assert!(html.contains("and <ins>large</ins> flowers"));
+        assert!(html.contains("<td class=\"field\">children</td><td colspan=\"2\">+ &quot;t5&quot;</td>"));
+        assert!(!html.contains("Books"));
+
+        let empty = render_html(&JsonValue::Object(Vec::new()), &EntityNames::empty(), "Dataset changes");
+        assert!(empty.contains("<p>No differences.</p>"));
+    }
+}
@@ -1,4 +1,4 @@
-pub use crate::errors::{JsonDiffError, JsonDiffErrorType};
+pub use crate::errors::{DiffInput, FieldError, FieldErrorKind, JsonDiffError, JsonDiffErrorType};
 
 #[cfg_attr(test, derive(Debug))]
 #[derive(PartialEq)]
@@ -158,7 +158,13 @@ impl<'a> Iterator for Tokenizer<'a> {
                 }
                 _ if !self.in_string && (c.is_ascii_digit() || c == b'-') => {
                     let num_start = self.i;
-                    while self.i + 1 < self.src.len() && (self.src[self.i + 1].is_ascii_digit() || self.src[self.i + 1] == b'.') {
+                    // Chiffres, point décimal et exposant (`1e-9`, `2.5E+3`)
+                    while self.i + 1 < self.src.len() {
+                        let next = self.src[self.i + 1];
+                        let exponent_sign = (next == b'-' || next == b'+') && matches!(self.src[self.i], b'e' | b'E');
+                        if !(next.is_ascii_digit() || matches!(next, b'.' | b'e' | b'E') || exponent_sign) {
+                            break;
+                        }
                         self.i += 1;
                     }
                     let token = Token {
@@ -188,6 +194,85 @@ pub enum JsonValue<'a> {
     Object(Vec<(&'a str, JsonValue<'a>)>),
 }
 
+impl<'a> JsonValue<'a> {
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue<'a>]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+// Encode un texte quelconque en littéral de chaîne JSON
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Décode les séquences d'échappement d'une chaîne telle que conservée par le parseur
+pub fn unescape(raw: &str) -> std::borrow::Cow<'_, str> {
+    if !raw.contains('\\') {
+        return std::borrow::Cow::Borrowed(raw);
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let mut code = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+                if (0xD800..0xDC00).contains(&code) {
+                    let rest = chars.as_str();
+                    if let Some(low) = rest.strip_prefix("\\u").and_then(|r| r.get(..4)).and_then(|h| u32::from_str_radix(h, 16).ok()) {
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                        chars = rest[6..].chars();
+                    }
+                }
+                out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+            }
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    std::borrow::Cow::Owned(out)
+}
+
 impl std::fmt::Display for JsonDiffErrorType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -196,19 +281,61 @@ impl std::fmt::Display for JsonDiffErrorType {
             JsonDiffErrorType::InvalidStructureUnclosed => write!(f, "Invalid structure: unclosed object or array"),
             JsonDiffErrorType::InvalidStructureUnexpectedToken => write!(f, "Invalid structure: unexpected token"),
             JsonDiffErrorType::InvalidStructureInvalidNumber => write!(f, "Invalid structure: invalid number"),
+            JsonDiffErrorType::InvalidOptions => write!(f, "Invalid diff options"),
+            JsonDiffErrorType::InvalidDataset => write!(f, "Invalid Hazo dataset"),
+            JsonDiffErrorType::InvalidXml => write!(f, "Invalid XML document"),
+            JsonDiffErrorType::TypeChanged => write!(f, "Type changed in strict collection"),
+        }
+    }
+}
+
+impl std::fmt::Display for DiffInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffInput::Old => write!(f, "old dataset"),
+            DiffInput::New => write!(f, "new dataset"),
         }
     }
 }
 
 impl std::fmt::Display for JsonDiffError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.error_type)
+        write!(f, "{}", self.error_type)?;
+        if let Some(property) = &self.property {
+            write!(f, ": '{}'", property)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " at '{}'", location)?;
+        }
+        if let Some(input) = &self.input {
+            write!(f, " in {}", input)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for FieldErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldErrorKind::Missing => write!(f, "missing field"),
+            FieldErrorKind::ExpectedString => write!(f, "expected a string"),
+            FieldErrorKind::ExpectedArray => write!(f, "expected an array"),
+            FieldErrorKind::ExpectedObject => write!(f, "expected an object"),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
     }
 }
 
-pub fn parse_json(input: &str) -> Result<JsonValue, JsonDiffError> {
+pub fn parse_json(input: &str) -> Result<JsonValue<'_>, JsonDiffError> {
     let tokenizer = Tokenizer::new(input);
     let mut stack_vec: Vec<JsonValue> = Vec::new();
+    // Position dans la pile du début de chaque tableau ou objet ouvert, et s'il s'agit d'un objet
+    let mut starts: Vec<(usize, bool)> = Vec::new();
 
     for token in tokenizer {
         match token.token_type {
@@ -217,7 +344,7 @@ pub fn parse_json(input: &str) -> Result<JsonValue, JsonDiffError> {
                 if s.starts_with('"') {
                     s = &s[1..];
                 }
-                if s.ends_with('"') && s.len() > 0 {
+                if s.ends_with('"') && !s.is_empty() {
                     s = &s[..s.len()-1];
                 }
                 stack_vec.push(JsonValue::String(s));
@@ -227,54 +354,34 @@ pub fn parse_json(input: &str) -> Result<JsonValue, JsonDiffError> {
                 if let Ok(num) = number_str.parse::<f64>() {
                     stack_vec.push(JsonValue::Number(num));
                 } else {
-                    return Err(JsonDiffError {
-                        error_type: JsonDiffErrorType::InvalidStructureInvalidNumber,
-                    });
+                    return Err(JsonDiffError::new(JsonDiffErrorType::InvalidStructureInvalidNumber));
                 }
             }
             TokenType::True => stack_vec.push(JsonValue::Boolean(true)),
             TokenType::False => stack_vec.push(JsonValue::Boolean(false)),
             TokenType::Null => stack_vec.push(JsonValue::Null),
             TokenType::Comma | TokenType::Colon => continue,
-            TokenType::ArrayStart => {
-                stack_vec.push(JsonValue::Array(Vec::new()));
-            }
+            TokenType::ArrayStart => starts.push((stack_vec.len(), false)),
+            TokenType::ObjectStart => starts.push((stack_vec.len(), true)),
             TokenType::ArrayEnd => {
-                let mut array = Vec::new();
-                while let Some(val) = stack_vec.pop() {
-                    match val {
-                        JsonValue::Array(_) => break, // Marqueur d'ouverture
-                        _ => array.push(val),
-                    }
-                }
-                array.reverse();
+                let Some((start, false)) = starts.pop() else {
+                    return Err(JsonDiffError::new(JsonDiffErrorType::InvalidStructureUnexpectedToken));
+                };
+                let array = stack_vec.split_off(start);
                 stack_vec.push(JsonValue::Array(array));
             }
-            TokenType::ObjectStart => {
-                stack_vec.push(JsonValue::Object(Vec::new()));
-            }
             TokenType::ObjectEnd => {
-                let mut temp_vals = Vec::new();
-                while let Some(val) = stack_vec.pop() {
-                    match val {
-                        JsonValue::Object(ref obj) if obj.is_empty() => {
-                            break;
-                        }
-                        _ => {
-                            temp_vals.push(val);
-                        }
-                    }
-                }
-                temp_vals.reverse();
+                let Some((start, true)) = starts.pop() else {
+                    return Err(JsonDiffError::new(JsonDiffErrorType::InvalidStructureUnexpectedToken));
+                };
+                let temp_vals = stack_vec.split_off(start);
                 let mut pairs = Vec::new();
                 let mut i = 0;
                 while i + 1 < temp_vals.len() {
                     let key = match &temp_vals[i] {
                         JsonValue::String(s) => *s,
                         _ => {
-                            return Err(JsonDiffError {
-                                error_type: JsonDiffErrorType::InvalidStructureObjectKey,
-                            });
+                            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidStructureObjectKey));
                         }
                     };
                     let value = temp_vals[i + 1].clone();
@@ -286,12 +393,10 @@ pub fn parse_json(input: &str) -> Result<JsonValue, JsonDiffError> {
         }
     }
 
-    if stack_vec.len() == 1 {
+    if stack_vec.len() == 1 && starts.is_empty() {
         Ok(stack_vec.pop().unwrap())
     } else {
-        Err(JsonDiffError {
-            error_type: JsonDiffErrorType::InvalidStructureUnclosed,
-        })
+        Err(JsonDiffError::new(JsonDiffErrorType::InvalidStructureUnclosed))
     }
 }
 
@@ -440,7 +545,6 @@ mod tests {
             }
             if JsonValue::Null != array[3] {
                 panic!("Expected fourth element to be null");
-            } else {
             }
         } else {
             panic!("Expected JSON value to be an array");
@@ -489,7 +593,7 @@ mod tests {
         let input = r#"{"key": "value", "number": 123, "boolean": true, "null_value": null"#;
         let json_value = parse_json(input);
         assert!(json_value.is_err());
-        if let Err(JsonDiffError { error_type }) = json_value {
+        if let Err(JsonDiffError { error_type, .. }) = json_value {
             match error_type {
                 JsonDiffErrorType::InvalidStructureUnclosed => {
                     // Erreur attendue pour la structure invalide
@@ -568,6 +672,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("plain"), "plain");
+        assert_eq!(unescape(r#"\"hello\"\n\\"#), "\"hello\"\n\\");
+        assert_eq!(unescape(r#"caf\u00e9 \ud83c\udf3f"#), "café 🌿");
+        let text = "tab\t \"quoted\" \\ \u{1}";
+        assert_eq!(escape(text), r#""tab\t \"quoted\" \\ \u0001""#);
+        assert_eq!(unescape(&escape(text)[1..escape(text).len() - 1]), text);
+    }
+
+    #[test]
+    fn test_parse_nested_empty_containers() {
+        let value = parse_json(r#"{"a":{},"b":[[],[1],{}],"c":[[2,3],4]}"#).unwrap();
+        assert_eq!(value.get("a"), Some(&JsonValue::Object(Vec::new())));
+        assert_eq!(value.get("b").and_then(|v| v.as_array()).map(|a| a.len()), Some(3));
+        assert_eq!(value.get("c").and_then(|v| v.as_array()).map(|a| a.len()), Some(2));
+        assert!(parse_json("[1]]").is_err());
+    }
+
+    #[test]
+    fn test_parse_mismatched_brackets() {
+        for input in [r#"{"taxons":[{"id":"t1"]}}"#, r#"[1}"#, r#"{"a":1]"#] {
+            let err = parse_json(input).unwrap_err();
+            assert!(matches!(err.error_type, JsonDiffErrorType::InvalidStructureUnexpectedToken), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_exponent_numbers() {
+        let value = parse_json("[1e-9,2.5E+3,-4e2]").unwrap();
+        assert_eq!(value, JsonValue::Array(vec![JsonValue::Number(1e-9), JsonValue::Number(2500.0), JsonValue::Number(-400.0)]));
+    }
+
     #[test]
     fn test_parse_nested_object() {
         let input = r#"{"outer": {"inner": "value"}}"#;
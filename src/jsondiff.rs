@@ -1,96 +1,154 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
 use crate::buffer::ByteBuffer;
-use crate::json::JsonValue;
+use crate::fuzzy::match_entities;
+use crate::json::{escape, unescape, JsonValue};
+use crate::moves::Move;
+use crate::options::{DiffOptions, TextGranularity};
+use crate::paths::JsonPath;
+use crate::seqdiff::{longest_increasing_subsequence, myers_diff, split_lines, split_words, EditKind};
 
 pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>, force_empty_array_diff: bool, flat_object_diff: bool, buf: &mut B) {
+    diff_json_value_with_options(a, b, force_empty_array_diff, flat_object_diff, &DiffOptions::default(), buf)
+}
+
+pub fn diff_json_value_with_options<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>, force_empty_array_diff: bool, flat_object_diff: bool, opts: &DiffOptions, buf: &mut B) {
+    diff_json_value_at(a, b, force_empty_array_diff, flat_object_diff, opts, &mut JsonPath::root(), buf);
+}
+
+// `path` désigne l'emplacement des valeurs comparées, pour les options définies par chemin.
+// Vrai si le diff contient un changement, le diff vide forcé d'un tableau n'en étant pas un
+pub(crate) fn diff_json_value_at<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>, force_empty_array_diff: bool, flat_object_diff: bool, opts: &DiffOptions, path: &mut JsonPath, buf: &mut B) -> bool {
+    if opts.filters_paths() && opts.is_excluded(path) {
+        return false;
+    }
     match (a, b) {
         (JsonValue::String(sa), JsonValue::String(sb)) => {
-            if sa != sb {
+            let changed = sa != sb;
+            if changed {
                 buf.push_str("{\"old\":");
-                buf.push_str(&json_escape(sa));
+                buf.push_str(&json_quote(sa));
                 buf.push_str(",\"new\":");
-                buf.push_str(&json_escape(sb));
+                buf.push_str(&json_quote(sb));
+                if let Some(text_diff) = opts.text_diff {
+                    let (ta, tb) = (unescape(sa), unescape(sb));
+                    if ta.chars().count().max(tb.chars().count()) >= text_diff.min_length {
+                        buf.push_str(",\"hunks\":");
+                        buf.push_str(&text_hunks(&ta, &tb, text_diff.granularity));
+                    }
+                }
                 buf.push(b'}');
             }
+            changed
         }
         (JsonValue::Number(na), JsonValue::Number(nb)) => {
-            if na != nb {
+            let changed = na != nb && !opts.tolerance_at(path).is_some_and(|t| t.accepts(*na, *nb));
+            if changed {
                 buf.push_str("{\"old\":");
                 buf.push_str(&na.to_string());
                 buf.push_str(",\"new\":");
                 buf.push_str(&nb.to_string());
                 buf.push(b'}');
             }
+            changed
         }
         (JsonValue::Boolean(ba), JsonValue::Boolean(bb)) => {
-            if ba != bb {
+            let changed = ba != bb;
+            if changed {
                 buf.push_str("{\"old\":");
                 buf.push_str(&ba.to_string());
                 buf.push_str(",\"new\":");
                 buf.push_str(&bb.to_string());
                 buf.push(b'}');
             }
+            changed
         }
-        (JsonValue::Null, JsonValue::Null) => {}
+        (JsonValue::Null, JsonValue::Null) => false,
         (JsonValue::Array(va), JsonValue::Array(vb)) => {
-            let min_len = va.len().min(vb.len());
-            let mut has_diff = false;
-            if va.len() != vb.len() {
-                has_diff = true;
+            if opts.is_set(path) {
+                return diff_set(va, vb, force_empty_array_diff, buf);
+            }
+            // Une séquence demandée explicitement prime sur l'appariement par clé
+            if opts.is_sequence(path) {
+                return diff_sequence(va, vb, force_empty_array_diff, buf);
             }
+            if let Some(key) = opts.identity_key.as_deref()
+                && let (Some(ka), Some(kb)) = (identity_keys(va, key), identity_keys(vb, key)) {
+                return diff_keyed_array(va, vb, &ka, &kb, key, &[], force_empty_array_diff, opts, path, buf);
+            }
+            let min_len = va.len().min(vb.len());
+            let excluded: Vec<bool> = (0..va.len().max(vb.len())).map(|i| {
+                if !opts.filters_paths() {
+                    return false;
+                }
+                path.push_index(i);
+                let excluded = opts.is_excluded(path);
+                path.pop();
+                excluded
+            }).collect();
+            let extra = |items: &[JsonValue<'a>], from: usize| -> Vec<String> {
+                items.iter().enumerate().skip(from).filter(|(i, _)| !excluded[*i]).filter_map(|(_, v)| language_view(v, opts)).collect()
+            };
+            let (extra_added, extra_removed) = (extra(vb, va.len()), extra(va, vb.len()));
+            let mut has_diff = !extra_added.is_empty() || !extra_removed.is_empty();
             for i in 0..min_len {
-                match (&va[i], &vb[i]) {
+                if has_diff && path.stops_at_first_change() {
+                    return true;
+                }
+                if excluded[i] {
+                    continue;
+                }
+                has_diff |= match (&va[i], &vb[i]) {
                     (JsonValue::Object(_), JsonValue::Object(_)) => {
-                        let mut tmp = String::new();
-                        diff_json_value(&va[i], &vb[i], false, true, &mut tmp);
-                        if !tmp.is_empty() {
-                            has_diff = true;
-                        }
-                    }
-                    _ => {
-                        if json_value_to_string(&va[i]) != json_value_to_string(&vb[i]) {
-                            has_diff = true;
-                        }
+                        path.push_index(i);
+                        let changed = diff_json_value_at(&va[i], &vb[i], false, true, opts, path, &mut String::new());
+                        path.pop();
+                        changed
                     }
-                }
+                    _ => element_differs(&va[i], &vb[i], i, opts, path),
+                };
+            }
+            if has_diff && path.stops_at_first_change() {
+                return true;
             }
             if !has_diff && !force_empty_array_diff {
-                return;
+                return false;
             }
             buf.push(b'{');
             buf.push_str("\"added\":[");
-            if vb.len() > va.len() {
-                for (i, be) in vb[va.len()..].iter().enumerate() {
-                    if i > 0 { buf.push(b','); }
-                    buf.push_str(&json_value_to_string(be));
-                }
-            }
+            buf.push_str(&extra_added.join(","));
             buf.push_str("]");
             buf.push_str(",\"removed\":[");
-            if va.len() > vb.len() {
-                for (i, ae) in va[vb.len()..].iter().enumerate() {
-                    if i > 0 { buf.push(b','); }
-                    buf.push_str(&json_value_to_string(ae));
-                }
-            }
+            buf.push_str(&extra_removed.join(","));
             buf.push_str("]");
             let mut first_mod = true;
             for i in 0..min_len {
+                if excluded[i] {
+                    continue;
+                }
                 match (&va[i], &vb[i]) {
                     (JsonValue::Object(_), JsonValue::Object(_)) => {
                         let mut tmp = String::new();
-                        diff_json_value(&va[i], &vb[i], false, true, &mut tmp);
-                        if !tmp.is_empty() {
+                        path.push_index(i);
+                        let changed = diff_json_value_at(&va[i], &vb[i], false, true, opts, path, &mut tmp);
+                        path.pop();
+                        if changed {
                             if first_mod {
                                 buf.push_str(",\"modified\":[");
                                 first_mod = false;
                             } else {
                                 buf.push(b',');
                             }
-                            buf.push_str(&tmp);
+                            // Les objets inchangés n'apparaissent pas : l'indice désigne l'élément modifié
+                            buf.push_str("{\"index\":");
+                            buf.push_str(&i.to_string());
+                            buf.push(b',');
+                            buf.push_str(&tmp[1..]);
                         }
                     }
                     _ => {
-                        if json_value_to_string(&va[i]) != json_value_to_string(&vb[i]) {
+                        if element_differs(&va[i], &vb[i], i, opts, path) {
                             if first_mod {
                                 buf.push_str(",\"modified\":[");
                                 first_mod = false;
@@ -102,6 +160,9 @@ pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>,
                             buf.push_str(&json_value_to_string(&va[i]));
                             buf.push_str(",\"new\":");
                             buf.push_str(&json_value_to_string(&vb[i]));
+                            path.push_index(i);
+                            push_type_change(&va[i], &vb[i], path, buf);
+                            path.pop();
                             buf.push(b'}');
                         }
                     }
@@ -111,39 +172,44 @@ pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>,
                 buf.push(b']');
             }
             buf.push(b'}');
+            has_diff
         }
         (JsonValue::Object(oa), JsonValue::Object(ob)) => {
             let mut added = Vec::new();
             let mut removed = Vec::new();
             let mut modified = Vec::new();
+            let mut added_values = Vec::new();
+            let mut removed_values = Vec::new();
             let mut tmp = String::new();
             for k in unique_sorted_keys(oa, ob) {
+                if flat_object_diff && !opts.keeps_entity_field(k) {
+                    continue;
+                }
+                if opts.filters_paths() {
+                    path.push(k);
+                    let excluded = opts.is_excluded(path);
+                    path.pop();
+                    if excluded {
+                        continue;
+                    }
+                }
                 let va = oa.iter().find(|(key, _)| key == &k).map(|(_, v)| v);
                 let vb = ob.iter().find(|(key, _)| key == &k).map(|(_, v)| v);
                 match (va, vb) {
-                    (Some(_), None) => {
-                        let mut s = String::new();
-                        s.push('"');
-                        s.push_str(&escape_key(k));
-                        s.push_str("\":");
-                        s.push_str(&json_value_to_string(va.unwrap()));
-                        removed.push(s);
-                    }
-                    (None, Some(_)) => {
-                        let mut s = String::new();
-                        s.push('"');
-                        s.push_str(&escape_key(k));
-                        s.push_str("\":");
-                        s.push_str(&json_value_to_string(vb.unwrap()));
-                        added.push(s);
-                    }
+                    (Some(va), None) => removed_values.push((k, va)),
+                    (None, Some(vb)) => added_values.push((k, vb)),
                     (Some(va), Some(vb)) => {
                         let mut sub_buf = String::new();
-                        crate::jsondiff::diff_json_value(va, vb, false, false, &mut sub_buf);
-                        if !sub_buf.is_empty() {
+                        path.push(k);
+                        let changed = diff_json_value_at(va, vb, false, false, opts, path, &mut sub_buf);
+                        path.pop();
+                        if changed {
+                            if path.stops_at_first_change() {
+                                return true;
+                            }
                             let mut s = String::new();
                             s.push('"');
-                            s.push_str(&escape_key(k));
+                            s.push_str(k);
                             s.push_str("\":");
                             s.push_str(&sub_buf);
                             modified.push(s);
@@ -152,6 +218,19 @@ pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>,
                     (None, None) => {}
                 }
             }
+            if path.stops_at_first_change() && !(added_values.is_empty() && removed_values.is_empty()) {
+                return true;
+            }
+            let renamed = if opts.detect_renames { pair_renamed_keys(&mut removed_values, &mut added_values) } else { Vec::new() };
+            for (k, v) in added_values {
+                added.push(format!("\"{}\":{}", k, json_value_to_string(v)));
+            }
+            for (k, v) in removed_values {
+                removed.push(format!("\"{}\":{}", k, json_value_to_string(v)));
+            }
+            if added.is_empty() && removed.is_empty() && renamed.is_empty() && modified.is_empty() {
+                return false;
+            }
             tmp.push('{');
             if !added.is_empty() {
                 tmp.push_str("\"added\":{");
@@ -164,8 +243,14 @@ pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>,
                 tmp.push_str(&removed.join(","));
                 tmp.push('}');
             }
-            if !modified.is_empty() {
+            if !renamed.is_empty() {
                 if !added.is_empty() || !removed.is_empty() { tmp.push(','); }
+                tmp.push_str("\"renamed\":{");
+                tmp.push_str(&renamed.join(","));
+                tmp.push('}');
+            }
+            if !modified.is_empty() {
+                if !added.is_empty() || !removed.is_empty() || !renamed.is_empty() { tmp.push(','); }
                 if flat_object_diff {
                     tmp.push_str(&modified.join(","));
                 } else {
@@ -176,41 +261,418 @@ pub fn diff_json_value<'a, B: ByteBuffer>(a: &JsonValue<'a>, b: &JsonValue<'a>,
             }
             tmp.push('}');
             buf.push_str(&tmp);
+            true
         }
         (a, b) => {
-            if json_value_to_string(a) != json_value_to_string(b) {
+            let changed = json_value_to_string(a) != json_value_to_string(b);
+            if changed {
                 buf.push_str("{\"old\":");
                 buf.push_str(&json_value_to_string(a));
                 buf.push_str(",\"new\":");
                 buf.push_str(&json_value_to_string(b));
+                push_type_change(a, b, path, buf);
                 buf.push(b'}');
             }
+            changed
         }
     }
 }
 
-fn escape_key(key: &str) -> String {
-    key.replace('"', "\\\"")
+// Insertions et suppressions minimales (Myers) avec leur position dans le nouveau et l'ancien tableau
+fn diff_sequence<B: ByteBuffer>(va: &[JsonValue], vb: &[JsonValue], force_empty_array_diff: bool, buf: &mut B) -> bool {
+    let a: Vec<String> = va.iter().map(json_value_to_string).collect();
+    let b: Vec<String> = vb.iter().map(json_value_to_string).collect();
+    let edits = myers_diff(&a, &b);
+    let positions = |kind: EditKind| -> Vec<String> {
+        edits.iter().filter(|e| e.kind == kind).flat_map(|e| match kind {
+            EditKind::Insert => e.new.clone().map(|j| format!("{{\"index\":{},\"value\":{}}}", j, b[j])).collect::<Vec<_>>(),
+            _ => e.old.clone().map(|i| format!("{{\"index\":{},\"value\":{}}}", i, a[i])).collect(),
+        }).collect()
+    };
+    let (inserted, deleted) = (positions(EditKind::Insert), positions(EditKind::Delete));
+    let changed = !inserted.is_empty() || !deleted.is_empty();
+    if !changed && !force_empty_array_diff {
+        return false;
+    }
+    buf.push_str("{\"inserted\":[");
+    buf.push_str(&inserted.join(","));
+    buf.push_str("],\"deleted\":[");
+    buf.push_str(&deleted.join(","));
+    buf.push_str("]}");
+    changed
 }
 
-fn json_escape(s: &str) -> String {
-    let mut out = String::with_capacity(s.len() + 2);
-    out.push('"');
-    for c in s.chars() {
-        match c {
-            '"' => out.push_str("\\\""),
-            '\n' => out.push_str("\\n"),
-            '\\' => out.push_str("\\\\"),
-            _ => out.push(c),
+// Forme canonique d'une valeur, indépendante de l'ordre des clés des objets
+pub(crate) fn canonical_json(v: &JsonValue) -> String {
+    match v {
+        JsonValue::Array(arr) => format!("[{}]", arr.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        JsonValue::Object(obj) => {
+            let mut entries: Vec<String> = obj.iter().map(|(k, v)| format!("\"{}\":{}", k, canonical_json(v))).collect();
+            entries.sort();
+            format!("{{{}}}", entries.join(","))
+        }
+        other => json_value_to_string(other),
+    }
+}
+
+// Comparaison ensembliste : l'ordre est ignoré, seuls comptent les éléments sans équivalent de
+// l'autre côté (les doublons sont comptés)
+fn diff_set<B: ByteBuffer>(va: &[JsonValue], vb: &[JsonValue], force_empty_array_diff: bool, buf: &mut B) -> bool {
+    let mut remaining: HashMap<String, usize> = HashMap::with_capacity(va.len());
+    for v in va {
+        *remaining.entry(canonical_json(v)).or_default() += 1;
+    }
+    let mut added = Vec::new();
+    for v in vb {
+        match remaining.get_mut(&canonical_json(v)) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(json_value_to_string(v)),
+        }
+    }
+    let mut removed = Vec::new();
+    for v in va {
+        if let Some(count) = remaining.get_mut(&canonical_json(v))
+            && *count > 0
+        {
+            *count -= 1;
+            removed.push(json_value_to_string(v));
+        }
+    }
+    let changed = !added.is_empty() || !removed.is_empty();
+    if !changed && !force_empty_array_diff {
+        return false;
+    }
+    buf.push_str("{\"added\":[");
+    buf.push_str(&added.join(","));
+    buf.push_str("],\"removed\":[");
+    buf.push_str(&removed.join(","));
+    buf.push_str("]}");
+    changed
+}
+
+// Morceaux égaux, insérés ou supprimés permettant de reconstruire les deux textes
+fn text_hunks(old: &str, new: &str, granularity: TextGranularity) -> String {
+    let split = match granularity {
+        TextGranularity::Words => split_words,
+        TextGranularity::Lines => split_lines,
+    };
+    let (a, b) = (split(old), split(new));
+    let mut s = String::from("[");
+    for (i, e) in myers_diff(&a, &b).into_iter().enumerate() {
+        if i > 0 { s.push(','); }
+        let text = match e.kind {
+            EditKind::Insert => b[e.new].concat(),
+            _ => a[e.old].concat(),
+        };
+        s.push_str("{\"op\":\"");
+        s.push_str(e.kind.name());
+        s.push_str("\",\"text\":");
+        s.push_str(&escape(&text));
+        s.push('}');
+    }
+    s.push(']');
+    s
+}
+
+// Une clé supprimée et une clé ajoutée de même valeur forment un renommage `"ancienne":"nouvelle"`,
+// si cette valeur est significative et n'apparaît qu'une fois de chaque côté ; les paires trouvées
+// sont retirées des deux listes
+fn pair_renamed_keys(removed: &mut Vec<(&str, &JsonValue)>, added: &mut Vec<(&str, &JsonValue)>) -> Vec<String> {
+    let canonical = |values: &[(&str, &JsonValue)]| -> Vec<Option<String>> {
+        values.iter().map(|(_, v)| (!is_trivial(v)).then(|| canonical_json(v))).collect()
+    };
+    let (old_values, new_values) = (canonical(removed), canonical(added));
+    let unique = |values: &[Option<String>], value: &String| {
+        let mut found = values.iter().enumerate().filter(|(_, v)| v.as_ref() == Some(value)).map(|(i, _)| i);
+        found.next().filter(|_| found.next().is_none())
+    };
+    let pairs: Vec<(usize, usize)> = old_values.iter().enumerate()
+        .filter_map(|(i, v)| {
+            let v = v.as_ref()?;
+            unique(&old_values, v)?;
+            Some((i, unique(&new_values, v)?))
+        })
+        .collect();
+    let renamed = pairs.iter().map(|&(i, j)| format!("\"{}\":\"{}\"", removed[i].0, added[j].0)).collect();
+    *removed = removed.iter().enumerate().filter(|(i, _)| !pairs.iter().any(|p| p.0 == *i)).map(|(_, e)| *e).collect();
+    *added = added.iter().enumerate().filter(|(j, _)| !pairs.iter().any(|p| p.1 == *j)).map(|(_, e)| *e).collect();
+    renamed
+}
+
+// Valeurs trop courantes pour qu'une égalité suffise à reconnaître un renommage
+fn is_trivial(v: &JsonValue) -> bool {
+    match v {
+        JsonValue::String(s) => s.is_empty(),
+        JsonValue::Number(n) => *n == 0.0,
+        JsonValue::Boolean(_) | JsonValue::Null => true,
+        JsonValue::Array(items) => items.is_empty(),
+        JsonValue::Object(fields) => fields.is_empty(),
+    }
+}
+
+pub(crate) fn type_name(v: &JsonValue) -> &'static str {
+    match v {
+        JsonValue::String(_) => "string",
+        JsonValue::Number(_) => "number",
+        JsonValue::Boolean(_) => "boolean",
+        JsonValue::Null => "null",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+// Changement de type signalé à part ; un passage depuis ou vers `null` reste un simple
+// changement de valeur (champ renseigné ou vidé)
+fn is_type_change(a: &JsonValue, b: &JsonValue) -> bool {
+    let (ta, tb) = (type_name(a), type_name(b));
+    ta != tb && ta != "null" && tb != "null"
+}
+
+fn push_type_change<B: ByteBuffer>(a: &JsonValue, b: &JsonValue, path: &mut JsonPath, buf: &mut B) {
+    if is_type_change(a, b) {
+        let (ta, tb) = (type_name(a), type_name(b));
+        path.record_type_change();
+        buf.push_str(",\"oldType\":\"");
+        buf.push_str(ta);
+        buf.push_str("\",\"newType\":\"");
+        buf.push_str(tb);
+        buf.push(b'"');
+    }
+}
+
+// Avec un filtre de langue, un élément ajouté ou supprimé ne garde que sa clé d'identité et les
+// champs de cette langue, et disparaît s'il n'en a aucun
+fn language_view(item: &JsonValue, opts: &DiffOptions) -> Option<String> {
+    if !shown_in_language(item, opts) {
+        return None;
+    }
+    let (Some(_), JsonValue::Object(fields)) = (&opts.language, item) else { return Some(json_value_to_string(item)) };
+    let key = opts.identity_key.as_deref();
+    let kept = fields.iter().filter(|(k, _)| Some(*k) == key || opts.keeps_entity_field(k)).cloned().collect();
+    Some(json_value_to_string(&JsonValue::Object(kept)))
+}
+
+fn shown_in_language(item: &JsonValue, opts: &DiffOptions) -> bool {
+    match (&opts.language, item) {
+        (Some(_), JsonValue::Object(fields)) => fields.iter().any(|(k, _)| opts.keeps_entity_field(k)),
+        _ => true,
+    }
+}
+
+// Comparaison d'éléments simples de même indice, avec la tolérance numérique éventuelle
+fn element_differs(a: &JsonValue, b: &JsonValue, index: usize, opts: &DiffOptions, path: &mut JsonPath) -> bool {
+    if let (JsonValue::Number(na), JsonValue::Number(nb)) = (a, b) {
+        path.push_index(index);
+        let tolerated = opts.tolerance_at(path).is_some_and(|t| t.accepts(*na, *nb));
+        path.pop();
+        return na != nb && !tolerated;
+    }
+    json_value_to_string(a) != json_value_to_string(b)
+}
+
+// Clés d'identité des éléments d'un tableau, si tous sont des objets portant une clé unique,
+// ou des valeurs simples distinctes qui sont alors leur propre identité
+fn identity_keys(items: &[JsonValue], key: &str) -> Option<Vec<String>> {
+    let mut keys = Vec::with_capacity(items.len());
+    let mut seen = HashMap::with_capacity(items.len());
+    let objects = matches!(items.first(), Some(JsonValue::Object(_)));
+    for item in items {
+        let k = match item {
+            JsonValue::Object(_) if objects => json_value_to_string(item.get(key)?),
+            JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Boolean(_) if !objects => json_value_to_string(item),
+            _ => return None,
+        };
+        if seen.insert(k.clone(), ()).is_some() {
+            return None;
+        }
+        keys.push(k);
+    }
+    Some(keys)
+}
+
+// Identifiant lisible d'une clé d'identité, sans les guillemets d'une chaîne
+fn key_label(k: &str) -> Cow<'_, str> {
+    match k.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+        Some(id) => unescape(id),
+        None => Cow::Borrowed(k),
+    }
+}
+
+// Appariement forcé par `key`, indépendamment de `identity_key` ; `None` si les éléments ne
+// portent pas tous une clé unique, sinon s'il y a un changement. Les déplacements dans la
+// hiérarchie rejoignent les changements d'ordre dans la liste `moved`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn diff_array_by_key<'a, B: ByteBuffer>(va: &[JsonValue<'a>], vb: &[JsonValue<'a>], key: &str, hierarchy_moves: &[Move], force_empty_array_diff: bool, opts: &DiffOptions, path: &mut JsonPath, buf: &mut B) -> Option<bool> {
+    let (ka, kb) = (identity_keys(va, key)?, identity_keys(vb, key)?);
+    Some(diff_keyed_array(va, vb, &ka, &kb, key, hierarchy_moves, force_empty_array_diff, opts, path, buf))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn diff_keyed_array<'a, B: ByteBuffer>(
+    va: &[JsonValue<'a>],
+    vb: &[JsonValue<'a>],
+    ka: &[String],
+    kb: &[String],
+    key: &str,
+    hierarchy_moves: &[Move],
+    force_empty_array_diff: bool,
+    opts: &DiffOptions,
+    path: &mut JsonPath,
+    buf: &mut B,
+) -> bool {
+    let old_index: HashMap<&str, usize> = ka.iter().enumerate().map(|(i, k)| (k.as_str(), i)).collect();
+    let new_index: HashMap<&str, usize> = kb.iter().enumerate().map(|(i, k)| (k.as_str(), i)).collect();
+    let kept = |path: &mut JsonPath, index: usize| {
+        if !opts.filters_paths() {
+            return true;
+        }
+        path.push_index(index);
+        let excluded = opts.is_excluded(path);
+        path.pop();
+        !excluded
+    };
+    let mut added: Vec<usize> = (0..vb.len()).filter(|&j| !old_index.contains_key(kb[j].as_str()) && kept(path, j)).collect();
+    let mut removed: Vec<usize> = (0..va.len()).filter(|&i| !new_index.contains_key(ka[i].as_str()) && kept(path, i)).collect();
+    let reorders = if opts.ignore_order || opts.language.is_some() { Vec::new() } else { reordered(&old_index, kb) };
+    // Un élément ajouté ou supprimé reste un changement, même apparié à un autre par ressemblance
+    if path.stops_at_first_change()
+        && (!hierarchy_moves.is_empty()
+            || !reorders.is_empty()
+            || added.iter().any(|&j| shown_in_language(&vb[j], opts))
+            || removed.iter().any(|&i| shown_in_language(&va[i], opts)))
+    {
+        return true;
+    }
+    let mut modified = Vec::new();
+    for (j, (v, k)) in vb.iter().zip(kb).enumerate() {
+        let Some(&i) = old_index.get(k.as_str()) else { continue };
+        let mut tmp = String::new();
+        path.push_entity(j, &key_label(k));
+        let changed = diff_json_value_at(&va[i], v, false, true, opts, path, &mut tmp);
+        path.pop();
+        if changed {
+            if path.stops_at_first_change() {
+                return true;
+            }
+            let mut s = String::with_capacity(tmp.len() + key.len() + k.len() + 4);
+            s.push_str("{\"");
+            s.push_str(key);
+            s.push_str("\":");
+            s.push_str(k);
+            s.push(',');
+            s.push_str(&tmp[1..]);
+            modified.push(s);
         }
     }
+    if let Some(fuzzy) = &opts.fuzzy_match
+        && !added.is_empty()
+        && !removed.is_empty()
+    {
+        for (i, j) in match_entities(va, vb, &removed, &added, key, fuzzy) {
+            if path.stops_at_first_change() {
+                return true;
+            }
+            modified.push(replacement_entry(&va[i], &vb[j], &ka[i], &kb[j], key, opts, j, path));
+            removed.retain(|&r| r != i);
+            added.retain(|&a| a != j);
+        }
+    }
+    let added: Vec<String> = added.into_iter().filter_map(|j| language_view(&vb[j], opts)).collect();
+    let removed: Vec<String> = removed.into_iter().filter_map(|i| language_view(&va[i], opts)).collect();
+    let changed = !(added.is_empty() && removed.is_empty() && modified.is_empty() && hierarchy_moves.is_empty() && reorders.is_empty());
+    if !changed && !force_empty_array_diff {
+        return false;
+    }
+    buf.push_str("{\"added\":[");
+    buf.push_str(&added.join(","));
+    buf.push_str("],\"removed\":[");
+    buf.push_str(&removed.join(","));
+    buf.push(b']');
+    if !modified.is_empty() {
+        buf.push_str(",\"modified\":[");
+        buf.push_str(&modified.join(","));
+        buf.push(b']');
+    }
+    let label = if matches!(va.first(), Some(JsonValue::Object(_))) { key } else { "value" };
+    push_moved(hierarchy_moves, &reorders, label, buf);
+    buf.push(b'}');
+    changed
+}
+
+// Changement d'ordre d'un élément apparié par clé
+struct IndexMove<'k> {
+    key: &'k str,
+    from: usize,
+    to: usize,
+}
+
+// Seul endroit où la liste `moved` est écrite. Elle mêle deux formes, que `index_move` distingue :
+// - `{<clé>,"fromIndex","toIndex"}` : changement d'ordre, désigné par la clé d'identité ou par
+//   `value` pour un tableau de valeurs simples ;
+// - `{"id","from","to","descendants"}` : entité changée de parent dans une hiérarchie, `from` et
+//   `to` étant les anciens et nouveaux parents (`null` à la racine), après les précédents.
+fn push_moved<B: ByteBuffer>(hierarchy_moves: &[Move], reorders: &[IndexMove], label: &str, buf: &mut B) {
+    if hierarchy_moves.is_empty() && reorders.is_empty() {
+        return;
+    }
+    let entries: Vec<String> = reorders.iter()
+        .map(|m| format!("{{\"{}\":{},\"fromIndex\":{},\"toIndex\":{}}}", label, m.key, m.from, m.to))
+        .chain(hierarchy_moves.iter().map(Move::to_json))
+        .collect();
+    buf.push_str(",\"moved\":[");
+    buf.push_str(&entries.join(","));
+    buf.push(b']');
+}
+
+fn without_key<'a>(v: &JsonValue<'a>, key: &str) -> JsonValue<'a> {
+    match v {
+        JsonValue::Object(fields) => JsonValue::Object(fields.iter().filter(|(k, _)| *k != key).cloned().collect()),
+        other => other.clone(),
+    }
+}
+
+// Entité ajoutée appariée à une entité supprimée : elle porte le nouvel identifiant, `replaces`
+// l'ancien, suivis du diff de leurs autres champs
+#[allow(clippy::too_many_arguments)]
+fn replacement_entry<'a>(old: &JsonValue<'a>, new: &JsonValue<'a>, old_key: &str, new_key: &str, key: &str, opts: &DiffOptions, index: usize, path: &mut JsonPath) -> String {
+    let mut tmp = String::new();
+    path.push_entity(index, &key_label(new_key));
+    diff_json_value_at(&without_key(old, key), &without_key(new, key), false, true, opts, path, &mut tmp);
+    path.pop();
+    let mut s = format!("{{\"{}\":{},\"replaces\":{}", key, new_key, old_key);
+    if tmp.is_empty() {
+        s.push('}');
+    } else {
+        s.push(',');
+        s.push_str(&tmp[1..]);
+    }
+    s
+}
+
+// Ensemble minimal d'éléments déplacés : ceux qui sortent d'une plus longue sous-suite
+// d'éléments communs restés dans le même ordre
+fn reordered<'k>(old_index: &HashMap<&str, usize>, kb: &'k [String]) -> Vec<IndexMove<'k>> {
+    let common: Vec<(usize, usize)> = kb.iter().enumerate().filter_map(|(j, k)| old_index.get(k.as_str()).map(|&i| (i, j))).collect();
+    let old_positions: Vec<usize> = common.iter().map(|&(i, _)| i).collect();
+    let mut stays = vec![false; common.len()];
+    for p in longest_increasing_subsequence(&old_positions) {
+        stays[p] = true;
+    }
+    common.iter().zip(stays).filter(|(_, stays)| !stays).map(|(&(i, j), _)| IndexMove { key: &kb[j], from: i, to: j }).collect()
+}
+
+// Les chaînes du parseur gardent leurs séquences d'échappement : il suffit de les encadrer
+fn json_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    out.push_str(s);
     out.push('"');
     out
 }
 
 pub fn json_value_to_string<'a>(v: &JsonValue<'a>) -> String {
     match v {
-        JsonValue::String(s) => json_escape(s),
+        JsonValue::String(s) => json_quote(s),
         JsonValue::Number(n) => n.to_string(),
         JsonValue::Boolean(b) => b.to_string(),
         JsonValue::Null => "null".to_string(),
@@ -229,7 +691,7 @@ pub fn json_value_to_string<'a>(v: &JsonValue<'a>) -> String {
             for (k, v) in obj {
                 let mut s = String::with_capacity(k.len() + 3);
                 s.push('"');
-                s.push_str(&escape_key(k));
+                s.push_str(k);
                 s.push_str("\":");
                 s.push_str(&json_value_to_string(v));
                 map.push(s);
@@ -257,6 +719,7 @@ fn unique_sorted_keys<'a>(oa: &'a [( &'a str, JsonValue<'a> )], ob: &'a [( &'a s
 mod tests {
     use super::*;
     use crate::json::parse_json;
+    use crate::options::{FuzzyMatch, TextDiff, Tolerance};
 
     fn diff_str(a: &str, b: &str, force: bool) -> String {
         let va = parse_json(a).unwrap();
@@ -319,10 +782,218 @@ mod tests {
         assert_eq!(diff_str(a, b, false), "{\"old\":\"foo\",\"new\":\"bar\"}");
     }
 
+    #[test]
+    fn test_diff_object_no_change() {
+        let a = "{\"a\":1,\"b\":{\"c\":[1,2]}}";
+        assert_eq!(diff_str(a, a, false), "");
+        let a = "[{\"id\":1,\"name\":\"A\"},{\"id\":2,\"name\":\"B\"}]";
+        let b = "[{\"id\":1,\"name\":\"A\"},{\"id\":2,\"name\":\"C\"}]";
+        assert_eq!(diff_str(a, b, false), "{\"added\":[],\"removed\":[],\"modified\":[{\"index\":1,\"name\":{\"old\":\"B\",\"new\":\"C\"}}]}");
+    }
+
+    #[test]
+    fn test_diff_escaped_strings_round_trip() {
+        let a = r#"{"name":"say \"hi\"\n"}"#;
+        let b = r#"{"name":"C:\\"}"#;
+        assert_eq!(diff_str(a, b, false), r#"{"modified":{"name":{"old":"say \"hi\"\n","new":"C:\\"}}}"#);
+    }
+
+    #[test]
+    fn test_diff_keyed_array() {
+        let opts = DiffOptions { identity_key: Some("id".to_string()), ..DiffOptions::default() };
+        let a = parse_json(r#"[{"id":"a","v":1},{"id":"b","v":2},{"id":"c","v":3}]"#).unwrap();
+        let b = parse_json(r#"[{"id":"b","v":2},{"id":"d","v":4},{"id":"a","v":5}]"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, true, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":[{"id":"d","v":4}],"removed":[{"id":"c","v":3}],"modified":[{"id":"a","v":{"old":1,"new":5}}],"moved":[{"id":"b","fromIndex":1,"toIndex":0}]}"#);
+
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &a, false, false, &opts, &mut buf);
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn test_diff_keyed_array_falls_back_to_index() {
+        let opts = DiffOptions { identity_key: Some("id".to_string()), ..DiffOptions::default() };
+        let a = parse_json(r#"[{"id":"a","v":1},{"v":2}]"#).unwrap();
+        let b = parse_json(r#"[{"id":"a","v":1},{"v":3}]"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":[],"removed":[],"modified":[{"index":1,"v":{"old":2,"new":3}}]}"#);
+    }
+
     #[test]
     fn test_diff_array_no_change_not_forced() {
         let a = "[1,2,3]";
         let b = "[1,2,3]";
         assert_eq!(diff_str(a, b, false), "");
     }
+
+    #[test]
+    fn test_diff_text_hunks() {
+        let va = parse_json(r#"{"detail":"Herbs with opposite leaves"}"#).unwrap();
+        let vb = parse_json(r#"{"detail":"Herbs with \"opposite\" leaves"}"#).unwrap();
+        let mut opts = DiffOptions { text_diff: Some(TextDiff { granularity: TextGranularity::Words, min_length: 10 }), ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert_eq!(buf, concat!(
+            r#"{"modified":{"detail":{"old":"Herbs with opposite leaves","new":"Herbs with \"opposite\" leaves","hunks":["#,
+            r#"{"op":"equal","text":"Herbs with "},{"op":"delete","text":"opposite"},{"op":"insert","text":"\"opposite\""},{"op":"equal","text":" leaves"}]}}}"#,
+        ));
+
+        opts.text_diff = Some(TextDiff { granularity: TextGranularity::Lines, min_length: 10 });
+        let va = parse_json(r#""line one\nline two\n""#).unwrap();
+        let vb = parse_json(r#""line one\nline 2\n""#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert!(buf.ends_with(r#""hunks":[{"op":"equal","text":"line one\n"},{"op":"delete","text":"line two\n"},{"op":"insert","text":"line 2\n"}]}"#));
+
+        opts.text_diff = Some(TextDiff::new(TextGranularity::Words));
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert!(!buf.contains("hunks"));
+    }
+
+    #[test]
+    fn test_diff_sequence() {
+        let va = parse_json(r#"{"path":["a","b","c","d"]}"#).unwrap();
+        let vb = parse_json(r#"{"path":["a","x","c","d","e"]}"#).unwrap();
+        let opts = DiffOptions { sequences: vec![crate::paths::PathPattern::parse("/path").unwrap()], ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"modified":{"path":{"inserted":[{"index":1,"value":"x"},{"index":4,"value":"e"}],"deleted":[{"index":1,"value":"b"}]}}}"#);
+
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &va, false, false, &opts, &mut buf);
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn test_diff_keyed_array_reorder() {
+        let va = parse_json(r#"[{"id":"a"},{"id":"b"},{"id":"c"},{"id":"d"}]"#).unwrap();
+        let vb = parse_json(r#"[{"id":"d"},{"id":"a"},{"id":"b"},{"id":"c"}]"#).unwrap();
+        let mut opts = DiffOptions { identity_key: Some("id".to_string()), ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":[],"removed":[],"moved":[{"id":"d","fromIndex":3,"toIndex":0}]}"#);
+
+        let va = parse_json(r#"["s1","s2","s3"]"#).unwrap();
+        let vb = parse_json(r#"["s3","s1","s4"]"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":["s4"],"removed":["s2"],"moved":[{"value":"s3","fromIndex":2,"toIndex":0}]}"#);
+
+        opts.ignore_order = true;
+        let mut buf = String::new();
+        diff_json_value_with_options(&va, &vb, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":["s4"],"removed":["s2"]}"#);
+    }
+
+    #[test]
+    fn test_diff_set() {
+        let opts = DiffOptions { sets: vec![crate::paths::PathPattern::parse("/refs").unwrap()], ..DiffOptions::default() };
+        let a = parse_json(r#"{"refs":["b1",{"x":1,"y":2},"b2","b2"],"other":["b1","b2"]}"#).unwrap();
+        let b = parse_json(r#"{"refs":[{"y":2,"x":1},"b2","b3","b1"],"other":["b2","b1"]}"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &opts, &mut buf);
+        assert!(buf.contains(r#""other":{"added":[],"removed":[],"modified":[{"old":"b1","new":"b2"}"#));
+        assert!(buf.contains(r#""refs":{"added":["b3"],"removed":["b2"]}"#));
+
+        let b = parse_json(r#"{"refs":["b2",{"x":1,"y":2},"b1","b2"],"other":["b1","b2"]}"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &opts, &mut buf);
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn test_diff_path_filters() {
+        let a = parse_json(r#"{"taxons":[{"id":"t1","name":"A","detail":"x","modifiedAt":1},{"id":"t2","name":"B","detail":"y"}],"books":[]}"#).unwrap();
+        let b = parse_json(r#"{"taxons":[{"id":"t1","name":"A2","detail":"x","modifiedAt":2},{"id":"t2","name":"B","detail":"z"}],"books":[{"id":"b1"}]}"#).unwrap();
+        let ignore = DiffOptions { ignore: vec![crate::paths::PathPattern::parse("/**/modifiedAt").unwrap()], ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &ignore, &mut buf);
+        assert!(!buf.contains("modifiedAt"));
+        assert!(buf.contains("\"name\":{\"old\":\"A\",\"new\":\"A2\"}"));
+
+        let only = DiffOptions { only: vec![crate::paths::PathPattern::parse("/taxons/*/detail").unwrap()], ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &only, &mut buf);
+        assert_eq!(buf, r#"{"modified":{"taxons":{"added":[],"removed":[],"modified":[{"index":1,"detail":{"old":"y","new":"z"}}]}}}"#);
+    }
+
+    #[test]
+    fn test_diff_numeric_tolerance() {
+        let a = parse_json(r#"{"ratio":0.30000000000000004,"height":[100,2.5],"count":3}"#).unwrap();
+        let b = parse_json(r#"{"ratio":0.3,"height":[100.5,2.5],"count":4}"#).unwrap();
+        let opts = DiffOptions {
+            tolerance: Some(Tolerance { absolute: 1e-9, relative: 0.0 }),
+            tolerances: vec![(crate::paths::PathPattern::parse("/height/*").unwrap(), Tolerance { absolute: 0.0, relative: 0.01 })],
+            ..DiffOptions::default()
+        };
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &opts, &mut buf);
+        assert_eq!(buf, r#"{"modified":{"count":{"old":3,"new":4}}}"#);
+    }
+
+    #[test]
+    fn test_diff_type_change() {
+        let a = parse_json(r#"{"height":12,"name":null,"tags":["a",1],"kind":"x"}"#).unwrap();
+        let b = parse_json(r#"{"height":"12","name":"A","tags":["a","1"],"kind":["x"]}"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &DiffOptions::default(), &mut buf);
+        assert!(buf.contains(r#""height":{"old":12,"new":"12","oldType":"number","newType":"string"}"#));
+        assert!(buf.contains(r#""name":{"old":null,"new":"A"}"#));
+        assert!(buf.contains(r#"{"old":1,"new":"1","oldType":"number","newType":"string"}"#));
+        assert!(buf.contains(r#""kind":{"old":"x","new":["x"],"oldType":"string","newType":"array"}"#));
+
+        let a = parse_json(r#"[{"id":"t1","name":"a","oldType":1},{"id":"t2","height":1}]"#).unwrap();
+        let b = parse_json(r#"[{"id":"t1","name":"b","oldType":2},{"id":"t2","height":"1"}]"#).unwrap();
+        let opts = DiffOptions { identity_key: Some("id".to_string()), ..DiffOptions::default() };
+        let mut path = JsonPath::from_key("taxons");
+        let mut buf = String::new();
+        diff_json_value_at(&a, &b, false, false, &opts, &mut path, &mut buf);
+        assert_eq!(path.take_type_change().as_deref(), Some("/taxons/t2/height"));
+
+        let b = parse_json(r#"[{"id":"t1","name":"b","oldType":2},{"id":"t2","height":2}]"#).unwrap();
+        diff_json_value_at(&a, &b, false, false, &opts, &mut path, &mut buf);
+        assert_eq!(path.take_type_change(), None);
+    }
+
+    #[test]
+    fn test_diff_renamed_keys() {
+        let a = parse_json(r#"{"id":"t1","vernacularName":"Fig","note":"x","old":1}"#).unwrap();
+        let b = parse_json(r#"{"id":"t1","vernacular":"Fig","remark":"y","new":1}"#).unwrap();
+        let opts = DiffOptions { detect_renames: true, ..DiffOptions::default() };
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, true, &opts, &mut buf);
+        assert_eq!(buf, r#"{"added":{"remark":"y"},"removed":{"note":"x"},"renamed":{"old":"new","vernacularName":"vernacular"}}"#);
+
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, true, &DiffOptions::default(), &mut buf);
+        assert!(!buf.contains("renamed"));
+
+        // Valeurs courantes ou ambiguës : pas de renommage
+        let a = parse_json(r#"{"id":"t1","hidden":false,"note":"","x":"Fig","y":"Fig"}"#).unwrap();
+        let b = parse_json(r#"{"id":"t1","visible":false,"remark":"","z":"Fig"}"#).unwrap();
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, true, &opts, &mut buf);
+        assert!(!buf.contains("renamed"), "{}", buf);
+    }
+
+    #[test]
+    fn test_diff_fuzzy_replacements() {
+        let a = parse_json(r#"[{"id":"t1","name":"Ficus carica","detail":"Fig"},{"id":"t2","name":"Morus alba"}]"#).unwrap();
+        let b = parse_json(r#"[{"id":"n1","name":"Ficus carica","detail":"Common fig"},{"id":"n2","name":"Quercus robur"}]"#).unwrap();
+        let opts = DiffOptions {
+            identity_key: Some("id".to_string()),
+            fuzzy_match: Some(FuzzyMatch { threshold: 0.6, weights: vec![("name".to_string(), 3.0)] }),
+            ..DiffOptions::default()
+        };
+        let mut buf = String::new();
+        diff_json_value_with_options(&a, &b, false, false, &opts, &mut buf);
+        assert_eq!(buf, concat!(
+            r#"{"added":[{"id":"n2","name":"Quercus robur"}],"removed":[{"id":"t2","name":"Morus alba"}],"#,
+            r#""modified":[{"id":"n1","replaces":"t1","detail":{"old":"Fig","new":"Common fig"}}]}"#,
+        ));
+    }
 }
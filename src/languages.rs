@@ -0,0 +1,126 @@
+use crate::json::JsonValue;
+use crate::jsondiff::json_value_to_string;
+use crate::options::DiffOptions;
+
+struct Change<'v, 'a> {
+    collection: &'a str,
+    id: Option<&'v JsonValue<'a>>,
+    field: &'a str,
+    old: Option<&'v JsonValue<'a>>,
+    new: Option<&'v JsonValue<'a>>,
+}
+
+fn entity_changes<'v, 'a>(collection: &'a str, value: &'v JsonValue<'a>, opts: &DiffOptions, changes: &mut Vec<Change<'v, 'a>>) {
+    let key = opts.identity_key.as_deref().unwrap_or("id");
+    let items = |name: &str| value.get(name).and_then(|v| v.as_array()).unwrap_or(&[]);
+    for (name, is_new) in [("added", true), ("removed", false)] {
+        for entity in items(name) {
+            let JsonValue::Object(fields) = entity else { continue };
+            for (field, v) in fields.iter().filter(|(f, _)| opts.language_of(f).is_some()) {
+                let (old, new) = if is_new { (None, Some(v)) } else { (Some(v), None) };
+                changes.push(Change { collection, id: entity.get(key), field, old, new });
+            }
+        }
+    }
+    for entry in items("modified") {
+        let JsonValue::Object(fields) = entry else { continue };
+        for (name, change) in fields {
+            match *name {
+                "added" | "removed" => {
+                    let JsonValue::Object(sub) = change else { continue };
+                    for (field, v) in sub.iter().filter(|(f, _)| opts.language_of(f).is_some()) {
+                        let (old, new) = if *name == "added" { (None, Some(v)) } else { (Some(v), None) };
+                        changes.push(Change { collection, id: entry.get(key), field, old, new });
+                    }
+                }
+                field if opts.language_of(field).is_some() => {
+                    changes.push(Change { collection, id: entry.get(key), field, old: change.get("old"), new: change.get("new") });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Regroupe les modifications des champs traduits par langue puis par collection
+pub fn group_by_language(diff: &JsonValue, opts: &DiffOptions) -> String {
+    let mut changes = Vec::new();
+    if let JsonValue::Object(collections) = diff {
+        for (collection, value) in collections.iter().filter(|(c, _)| opts.is_collection(c)) {
+            entity_changes(collection, value, opts, &mut changes);
+        }
+    }
+    let value_or_null = |v: Option<&JsonValue>| v.map_or("null".to_string(), json_value_to_string);
+    let mut s = String::from("{");
+    let mut first_lang = true;
+    for lang in &opts.languages {
+        if opts.language.as_ref().is_some_and(|code| code != &lang.code) {
+            continue;
+        }
+        let lang_changes: Vec<&Change> = changes.iter().filter(|c| opts.language_of(c.field) == Some(lang.code.as_str())).collect();
+        if lang_changes.is_empty() {
+            continue;
+        }
+        if !first_lang { s.push(','); } else { first_lang = false; }
+        s.push_str(&json_value_to_string(&JsonValue::String(&lang.code)));
+        s.push_str(":{");
+        let mut collections: Vec<&str> = Vec::new();
+        for c in &lang_changes {
+            if !collections.contains(&c.collection) {
+                collections.push(c.collection);
+            }
+        }
+        for (i, collection) in collections.iter().enumerate() {
+            if i > 0 { s.push(','); }
+            s.push_str(&json_value_to_string(&JsonValue::String(collection)));
+            s.push_str(":[");
+            for (j, c) in lang_changes.iter().filter(|c| c.collection == *collection).enumerate() {
+                if j > 0 { s.push(','); }
+                s.push_str("{\"id\":");
+                s.push_str(&value_or_null(c.id));
+                s.push_str(",\"field\":");
+                s.push_str(&json_value_to_string(&JsonValue::String(c.field)));
+                s.push_str(",\"old\":");
+                s.push_str(&value_or_null(c.old));
+                s.push_str(",\"new\":");
+                s.push_str(&value_or_null(c.new));
+                s.push('}');
+            }
+            s.push(']');
+        }
+        s.push('}');
+    }
+    s.push('}');
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DIFF: &str = r#"{
+        "taxons":{"added":[{"id":"t9","name":"Ficus","nameCN":"榕属","detail":""}],"removed":[],
+                  "modified":[{"id":"t1","detail":{"old":"a","new":"b"},"nameEN":{"old":"","new":"Acanthus family"},"added":{"nameCN":"爵床科"}}]},
+        "states":{"added":[],"removed":[],"modified":[{"id":"s1","nameCN":{"old":"红","new":"紅"}}]}
+    }"#;
+
+    #[test]
+    fn test_group_by_language() {
+        let diff = parse_json(DIFF).unwrap();
+        let grouped = group_by_language(&diff, &DiffOptions::hazo());
+        assert_eq!(grouped, concat!(
+            r#"{"default":{"taxons":[{"id":"t9","field":"name","old":null,"new":"Ficus"}]},"#,
+            r#""en":{"taxons":[{"id":"t1","field":"nameEN","old":"","new":"Acanthus family"}]},"#,
+            r#""cn":{"taxons":[{"id":"t9","field":"nameCN","old":null,"new":"榕属"},{"id":"t1","field":"nameCN","old":null,"new":"爵床科"}],"#,
+            r#""states":[{"id":"s1","field":"nameCN","old":"红","new":"紅"}]}}"#,
+        ));
+    }
+
+    #[test]
+    fn test_group_single_language() {
+        let diff = parse_json(DIFF).unwrap();
+        let opts = DiffOptions { language: Some("en".to_string()), ..DiffOptions::hazo() };
+        assert_eq!(group_by_language(&diff, &opts), r#"{"en":{"taxons":[{"id":"t1","field":"nameEN","old":"","new":"Acanthus family"}]}}"#);
+    }
+}
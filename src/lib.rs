@@ -14,21 +14,109 @@ mod json;
 mod jsondiff;
 mod errors;
 mod buffer;
-use crate::json::{parse_json, JsonDiffErrorType, JsonValue};
-use crate::jsondiff::diff_json_value;
-use crate::errors::JsonDiffError;
+mod options;
+mod hazo;
+mod validate;
+mod moves;
+mod summary;
+mod languages;
+mod xml;
+mod sdd;
+mod delta;
+mod render;
+mod seqdiff;
+mod html;
+mod changelog;
+mod paths;
+mod unicode;
+mod normalize;
+mod fuzzy;
+mod stats;
+pub use crate::json::{parse_json, JsonValue};
+use crate::jsondiff::json_value_to_string;
+use crate::moves::mask_moves;
+use crate::errors::JsonDiffErrorType;
+pub use crate::errors::{DiffInput, FieldError, FieldErrorKind, JsonDiffError};
+pub use crate::hazo::{Book, Character, Dataset, Description, State, Taxon};
+pub use crate::jsondiff::{diff_json_value, diff_json_value_with_options};
+use crate::jsondiff::{diff_array_by_key, diff_json_value_at};
+pub use crate::moves::{detect_moves, Move};
+pub use crate::summary::{render_summary, summarize, DomainEvent, SummaryFormat};
+pub use crate::validate::{issues_to_json, issues_to_text, validate, validate_json_str, Issue, IssueKind};
+pub use crate::languages::group_by_language;
+pub use crate::render::{render_text, DiffFormat, EntityNames};
+pub use crate::html::render_html;
+pub use crate::changelog::render_changelog;
+pub use crate::delta::{changed_items, dataset_to_delta, DeltaFiles};
+pub use crate::sdd::{dataset_to_sdd, sdd_to_hazo_json, EntitySelection};
+pub use crate::paths::{JsonPath, PathPattern};
+pub use crate::normalize::normalize_json;
+use crate::normalize::{normalize_value, normalized_strings};
+pub use crate::stats::{diff_stats, CollectionStats, DiffStats, PropertyStats};
+pub use crate::options::{DiffOptions, FuzzyMatch, Language, MissingCollectionPolicy, Normalization, TextDiff, TextGranularity, Tolerance};
+use std::borrow::Cow;
 use crate::buffer::ByteBuffer;
 
 fn get_prop<'a>(
     obj: &'a JsonValue<'a>,
     key: &str,
 ) -> Option<&'a JsonValue<'a>> {
-    match obj {
-        JsonValue::Object(fields) => {
-            fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    obj.get(key)
+}
+
+static EMPTY_COLLECTION: JsonValue<'static> = JsonValue::Array(Vec::new());
+
+fn get_collection<'a>(
+    root: &'a JsonValue<'a>,
+    key: &str,
+    input: DiffInput,
+    opts: &DiffOptions,
+) -> Result<&'a JsonValue<'a>, JsonDiffError> {
+    match (get_prop(root, key), opts.missing_collections) {
+        (Some(value), _) => Ok(value),
+        (None, MissingCollectionPolicy::TreatAsEmpty) => Ok(&EMPTY_COLLECTION),
+        (None, MissingCollectionPolicy::Strict) => Err(JsonDiffError::property_missing(key, input)),
+    }
+}
+
+fn root_properties<'a>(root: &JsonValue<'a>, opts: &DiffOptions) -> JsonValue<'a> {
+    match root {
+        JsonValue::Object(fields) => JsonValue::Object(
+            fields.iter().filter(|(k, _)| !opts.is_collection(k)).cloned().collect(),
+        ),
+        _ => JsonValue::Object(Vec::new()),
+    }
+}
+
+// Clés inconnues ajoutées, supprimées ou modifiées ; celles identiques des deux côtés ne sont pas signalées
+fn unknown_root_keys<'a>(old_root: &JsonValue<'a>, new_root: &JsonValue<'a>, opts: &DiffOptions) -> Vec<&'a str> {
+    let mut keys = Vec::new();
+    for root in [old_root, new_root] {
+        if let JsonValue::Object(fields) = root {
+            keys.extend(fields.iter().map(|(k, _)| *k).filter(|k| !opts.is_collection(k) && !opts.is_known_property(k)));
         }
-        _ => None,
     }
+    keys.retain(|k| old_root.get(k) != new_root.get(k));
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+// Écrit le diff d'une collection et indique s'il contient un changement
+fn diff_collection<'a>(name: &str, old: &JsonValue<'a>, new: &JsonValue<'a>, opts: &DiffOptions, path: &mut JsonPath, buf: &mut String) -> bool {
+    // Un filtre de langue ne garde que des champs : les déplacements n'en font pas partie
+    if opts.is_hierarchy(name)
+        && opts.language.is_none()
+        && let (Some(old_items), Some(new_items)) = (old.as_array(), new.as_array()) {
+        let moves = detect_moves(old_items, new_items);
+        // Le masque ne suit pas l'ordre de la nouvelle collection : les entités sont appariées par `id`,
+        // et des identifiants en double ramènent au diff sans déplacements
+        if !moves.is_empty()
+            && let Some(changed) = diff_array_by_key(&mask_moves(old_items, new_items, &moves), new_items, "id", &moves, true, opts, path, buf) {
+            return changed;
+        }
+    }
+    diff_json_value_at(old, new, true, false, opts, path, buf)
 }
 
 pub fn diff_json_strs<B: ByteBuffer>(
@@ -36,20 +124,96 @@ pub fn diff_json_strs<B: ByteBuffer>(
     new_json: &str,
     buf: &mut B,
 ) -> Result<(), JsonDiffError> {
-    let old_val = parse_json(old_json)?;
-    let new_val = parse_json(new_json)?;
+    diff_json_strs_with_options(old_json, new_json, &DiffOptions::hazo(), buf)
+}
+
+pub fn diff_json_strs_with_options<B: ByteBuffer>(
+    old_json: &str,
+    new_json: &str,
+    opts: &DiffOptions,
+    buf: &mut B,
+) -> Result<(), JsonDiffError> {
+    write_diff(old_json, new_json, opts, false, buf).map(|_| ())
+}
+
+// Écrit le diff et indique s'il contient un changement ; avec `first_change_only`, le parcours
+// s'arrête à la première différence et le diff écrit est incomplet
+fn write_diff<B: ByteBuffer>(old_json: &str, new_json: &str, opts: &DiffOptions, first_change_only: bool, buf: &mut B) -> Result<bool, JsonDiffError> {
+    let old_val = parse_json(old_json).map_err(|e| e.in_input(DiffInput::Old))?;
+    let new_val = parse_json(new_json).map_err(|e| e.in_input(DiffInput::New))?;
+    if opts.normalization.is_active() {
+        let n = &opts.normalization;
+        let (old_strings, new_strings) = (normalized_strings(&old_val, n), normalized_strings(&new_val, n));
+        let (old_normalized, new_normalized) = (normalize_value(&old_val, n, &old_strings), normalize_value(&new_val, n, &new_strings));
+        return diff_dataset_values(&old_normalized, &new_normalized, opts, first_change_only, buf);
+    }
+    diff_dataset_values(&old_val, &new_val, opts, first_change_only, buf)
+}
+
+// Vrai dès la première différence trouvée, sans construire le diff complet
+pub fn json_strs_differ(old_json: &str, new_json: &str, opts: &DiffOptions) -> Result<bool, JsonDiffError> {
+    write_diff(old_json, new_json, opts, true, &mut String::new())
+}
+
+// Une collection stricte est parcourue en entier pour y relever un changement de type
+fn diff_dataset_collection(prop: &str, old_p: &JsonValue, new_p: &JsonValue, opts: &DiffOptions, first_change_only: bool) -> Result<(bool, String), JsonDiffError> {
+    let mut path = JsonPath::from_key(prop);
+    if first_change_only && !opts.is_strict_types(prop) {
+        path.stop_at_first_change();
+    }
+    let mut prop_buf = String::new();
+    let changed = diff_collection(prop, old_p, new_p, opts, &mut path, &mut prop_buf);
+    if let Some(location) = path.take_type_change().filter(|_| opts.is_strict_types(prop)) {
+        return Err(JsonDiffError::type_changed(location));
+    }
+    Ok((changed, prop_buf))
+}
+
+// Le diff d'une collection garde ses listes `added` et `removed`, même vides
+fn collection_changed(diff: &JsonValue) -> bool {
+    match diff {
+        JsonValue::Object(parts) => parts.iter().any(|(_, v)| match v {
+            JsonValue::Array(items) => !items.is_empty(),
+            JsonValue::Object(fields) => !fields.is_empty(),
+            _ => true,
+        }),
+        _ => true,
+    }
+}
+
+fn diff_has_changes(diff: &JsonValue) -> bool {
+    match diff {
+        JsonValue::Object(parts) => parts.iter().any(|(_, v)| collection_changed(v)),
+        _ => true,
+    }
+}
+
+fn diff_dataset_values<B: ByteBuffer>(
+    old_val: &JsonValue,
+    new_val: &JsonValue,
+    opts: &DiffOptions,
+    first_change_only: bool,
+    buf: &mut B,
+) -> Result<bool, JsonDiffError> {
+    // Les collections manquantes restent des erreurs, quel que soit l'ordre des différences
+    let mut collections = Vec::with_capacity(opts.collections.len());
+    for prop in &opts.collections {
+        collections.push((prop, get_collection(old_val, prop, DiffInput::Old, opts)?, get_collection(new_val, prop, DiffInput::New, opts)?));
+    }
     let mut tmp_buf = String::new();
     tmp_buf.push('{');
     let mut first = true;
-    for prop in ["taxons", "characters", "states", "books"] {
-        let old_p = get_prop(&old_val, prop).ok_or_else(|| JsonDiffError {
-            error_type: JsonDiffErrorType::PropertyMissing,
-        })?;
-        let new_p = get_prop(&new_val, prop).ok_or_else(|| JsonDiffError {
-            error_type: JsonDiffErrorType::PropertyMissing,
-        })?;
-        let mut prop_buf = String::new();
-        diff_json_value(old_p, new_p, true, false, &mut prop_buf);
+    let mut changed = false;
+    for (prop, old_p, new_p) in collections {
+        // Des valeurs égales ne produisent aucun changement
+        if first_change_only && old_p == new_p {
+            continue;
+        }
+        let (prop_changed, prop_buf) = diff_dataset_collection(prop, old_p, new_p, opts, first_change_only)?;
+        changed |= prop_changed;
+        if changed && first_change_only {
+            return Ok(true);
+        }
         if !prop_buf.is_empty() {
             if !first { tmp_buf.push(','); } else { first = false; }
             tmp_buf.push('"');
@@ -58,18 +222,218 @@ pub fn diff_json_strs<B: ByteBuffer>(
             tmp_buf.push_str(&prop_buf);
         }
     }
+    // Les propriétés du jeu de données ne relèvent d'aucune langue
+    if opts.language.is_some() {
+        if !first {
+            tmp_buf.push('}');
+            buf.push_str(&tmp_buf);
+        }
+        return Ok(changed);
+    }
+    let mut props_buf = String::new();
+    let mut path = JsonPath::root();
+    if first_change_only {
+        path.stop_at_first_change();
+    }
+    if diff_json_value_at(&root_properties(old_val, opts), &root_properties(new_val, opts), false, false, opts, &mut path, &mut props_buf) {
+        changed = true;
+        if !first { tmp_buf.push(','); } else { first = false; }
+        tmp_buf.push_str("\"properties\":");
+        tmp_buf.push_str(&props_buf);
+    }
+    let unknown = unknown_root_keys(old_val, new_val, opts);
+    if !unknown.is_empty() {
+        changed = true;
+        if !first { tmp_buf.push(','); } else { first = false; }
+        tmp_buf.push_str("\"unknown\":");
+        tmp_buf.push_str(&json_value_to_string(&JsonValue::Array(unknown.into_iter().map(JsonValue::String).collect())));
+    }
     tmp_buf.push('}');
     if !first {
         buf.push_str(&tmp_buf);
     }
+    Ok(changed)
+}
+
+// Les sorties par entité (résumé, statistiques, rendus, exports) apparient les éléments par `id`
+// quand aucune clé d'identité n'est choisie ; la sortie JSON reste positionnelle
+fn entity_options(opts: &DiffOptions) -> Cow<'_, DiffOptions> {
+    match opts.identity_key {
+        Some(_) => Cow::Borrowed(opts),
+        None => Cow::Owned(DiffOptions { identity_key: Some("id".to_string()), ..opts.clone() }),
+    }
+}
+
+// Diff par entité analysé pour les rendus et exports, `None` quand les jeux sont identiques ;
+// `storage` garde le texte sur lequel pointent les valeurs
+fn parsed_diff<'d>(old_json: &str, new_json: &str, opts: &DiffOptions, storage: &'d mut String) -> Result<Option<JsonValue<'d>>, JsonDiffError> {
+    diff_json_strs_with_options(old_json, new_json, &entity_options(opts), storage)?;
+    let diff: &'d String = storage;
+    if diff.is_empty() { Ok(None) } else { parse_json(diff).map(Some) }
+}
+
+// Le résumé s'appuie sur les identifiants des entités
+pub fn summarize_json_strs<B: ByteBuffer>(
+    old_json: &str,
+    new_json: &str,
+    opts: &DiffOptions,
+    format: SummaryFormat,
+    buf: &mut B,
+) -> Result<(), JsonDiffError> {
+    let mut storage = String::new();
+    let events = parsed_diff(old_json, new_json, opts, &mut storage)?.map_or(Vec::new(), |diff| summarize(&diff));
+    buf.push_str(&render_summary(&events, format));
     Ok(())
 }
 
-#[wasm_bindgen]
-pub fn diff_hazo_json_strs(old_json: &str, new_json: &str, out: &Uint8Array) -> f64 {
-    use crate::buffer::JsByteBuffer;
-    let mut buf = JsByteBuffer::new(1024);
-    let r = diff_json_strs(old_json, new_json, &mut buf);
+// Comme le résumé, les statistiques comptent des entités
+pub fn diff_stats_json_strs(old_json: &str, new_json: &str, opts: &DiffOptions) -> Result<DiffStats, JsonDiffError> {
+    let mut storage = String::new();
+    let diff = parsed_diff(old_json, new_json, opts, &mut storage)?.unwrap_or(JsonValue::Object(Vec::new()));
+    Ok(diff_stats(&diff, old_json.len(), new_json.len()))
+}
+
+// Rendu du diff dans le format demandé ; `color` n'affecte que le format texte
+pub fn render_diff_json_strs<B: ByteBuffer>(
+    old_json: &str,
+    new_json: &str,
+    opts: &DiffOptions,
+    format: DiffFormat,
+    color: bool,
+    buf: &mut B,
+) -> Result<bool, JsonDiffError> {
+    if format == DiffFormat::Json {
+        return write_diff(old_json, new_json, opts, false, buf);
+    }
+    let mut storage = String::new();
+    let diff = match parsed_diff(old_json, new_json, opts, &mut storage)? {
+        Some(diff) => diff,
+        // Un rapport HTML reste un document complet même sans différence
+        None if format == DiffFormat::Html => JsonValue::Object(Vec::new()),
+        None => return Ok(false),
+    };
+    let old_val = parse_json(old_json).map_err(|e| e.in_input(DiffInput::Old))?;
+    let new_val = parse_json(new_json).map_err(|e| e.in_input(DiffInput::New))?;
+    let entity_opts = entity_options(opts);
+    let names = EntityNames::new(&old_val, &new_val, &entity_opts);
+    match format {
+        DiffFormat::Html => buf.push_str(&render_html(&diff, &names, "Dataset changes")),
+        DiffFormat::Markdown => buf.push_str(&render_changelog(&diff, &names)),
+        _ => buf.push_str(&render_text(&diff, &names, color)),
+    }
+    Ok(diff_has_changes(&diff))
+}
+
+pub fn group_json_strs_by_language<B: ByteBuffer>(
+    old_json: &str,
+    new_json: &str,
+    opts: &DiffOptions,
+    buf: &mut B,
+) -> Result<bool, JsonDiffError> {
+    let mut storage = String::new();
+    match parsed_diff(old_json, new_json, opts, &mut storage)? {
+        Some(diff) => {
+            // Les propriétés du jeu de données ne relèvent d'aucune langue : seul compte le regroupement
+            let grouped = group_by_language(&diff, &entity_options(opts));
+            let changed = grouped != "{}";
+            buf.push_str(&grouped);
+            Ok(changed)
+        }
+        None => {
+            buf.push_str("{}");
+            Ok(false)
+        }
+    }
+}
+
+fn load_dataset<'a>(value: &JsonValue<'a>) -> Result<Dataset<'a>, JsonDiffError> {
+    Dataset::from_json(value).map_err(|_| JsonDiffError::new(JsonDiffErrorType::InvalidDataset))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_iso8601() -> String {
+    let secs = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86400, secs % 86400);
+    // Conversion jours depuis 1970 -> date civile (algorithme de H. Hinnant)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_iso8601() -> String {
+    String::from(js_sys::Date::new_0().to_iso_string())
+}
+
+pub fn export_sdd_json_str<B: ByteBuffer>(json: &str, buf: &mut B) -> Result<(), JsonDiffError> {
+    let value = parse_json(json)?;
+    let dataset = load_dataset(&value)?;
+    buf.push_str(&dataset_to_sdd(&dataset, None, &now_iso8601()));
+    Ok(())
+}
+
+// Exporte de la nouvelle version uniquement les entités touchées par le diff
+pub fn export_sdd_changes_json_strs<B: ByteBuffer>(
+    old_json: &str,
+    new_json: &str,
+    opts: &DiffOptions,
+    buf: &mut B,
+) -> Result<(), JsonDiffError> {
+    let mut storage = String::new();
+    let selection = parsed_diff(old_json, new_json, opts, &mut storage)?.map_or(EntitySelection::default(), |diff| EntitySelection::from_diff(&diff));
+    let value = parse_json(new_json)?;
+    let dataset = load_dataset(&value)?;
+    buf.push_str(&dataset_to_sdd(&dataset, Some(&selection), &now_iso8601()));
+    Ok(())
+}
+
+pub fn export_delta_json_str(json: &str) -> Result<DeltaFiles, JsonDiffError> {
+    let value = parse_json(json)?;
+    let dataset = load_dataset(&value)?;
+    Ok(dataset_to_delta(&dataset, None))
+}
+
+// Régénère uniquement les items DELTA des taxons touchés par le diff entre les deux versions
+pub fn export_delta_changes_json_strs(old_json: &str, new_json: &str, opts: &DiffOptions) -> Result<DeltaFiles, JsonDiffError> {
+    let mut storage = String::new();
+    let selection = parsed_diff(old_json, new_json, opts, &mut storage)?.map_or(EntitySelection::default(), |diff| EntitySelection::from_diff(&diff));
+    let old_value = parse_json(old_json)?;
+    let old = load_dataset(&old_value)?;
+    let new_value = parse_json(new_json)?;
+    let new = load_dataset(&new_value)?;
+    let only = changed_items(&old, &new, &selection.taxons);
+    Ok(dataset_to_delta(&new, Some(&only)))
+}
+
+// Ce qu'un export SDD garde d'un jeu de données, relu au format Hazo : les champs que SDD ne
+// représente pas disparaissent, les listes vides et textes vides ne sont pas recréés
+pub fn sdd_view_json_str(json: &str) -> Result<String, JsonDiffError> {
+    let value = parse_json(json)?;
+    let dataset = load_dataset(&value)?;
+    sdd_to_hazo_json(&dataset_to_sdd(&dataset, None, ""))
+}
+
+// Diff d'un jeu de données Hazo (ancien) avec un document SDD (nouveau) converti au format Hazo ;
+// seule la partie de l'ancien jeu que SDD sait représenter est comparée
+pub fn diff_json_str_with_sdd<B: ByteBuffer>(
+    old_json: &str,
+    new_sdd: &str,
+    opts: &DiffOptions,
+    buf: &mut B,
+) -> Result<(), JsonDiffError> {
+    let new_json = sdd_to_hazo_json(new_sdd)?;
+    let old_json = sdd_view_json_str(old_json).map_err(|e| e.in_input(DiffInput::Old))?;
+    diff_json_strs_with_options(&old_json, &new_json, opts, buf)
+}
+
+fn write_diff_result(r: Result<(), JsonDiffError>, buf: &buffer::JsByteBuffer, out: &Uint8Array) -> f64 {
     if let Err(e) = r {
         return -1.0 - (e.error_type as i8 as f64);
     }
@@ -78,6 +442,128 @@ pub fn diff_hazo_json_strs(old_json: &str, new_json: &str, out: &Uint8Array) ->
     buf.len() as f64
 }
 
+#[wasm_bindgen]
+pub fn diff_hazo_json_strs(old_json: &str, new_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = diff_json_strs(old_json, new_json, &mut buf);
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn diff_hazo_json_strs_with_options(old_json: &str, new_json: &str, options_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| diff_json_strs_with_options(old_json, new_json, &opts, &mut buf));
+    write_diff_result(r, &buf, out)
+}
+
+// Objet JS des statistiques, ou code d'erreur négatif comme les autres fonctions
+#[wasm_bindgen]
+pub fn diff_stats_hazo_json_strs(old_json: &str, new_json: &str, options_json: &str) -> JsValue {
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| diff_stats_json_strs(old_json, new_json, &opts));
+    match r {
+        Ok(stats) => js_sys::JSON::parse(&stats.to_json()).unwrap_or(JsValue::NULL),
+        Err(e) => JsValue::from_f64(-1.0 - (e.error_type as i8 as f64)),
+    }
+}
+
+#[wasm_bindgen]
+pub fn summarize_hazo_json_strs(old_json: &str, new_json: &str, options_json: &str, format: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| {
+            let format = SummaryFormat::from_name(format).ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidOptions))?;
+            summarize_json_strs(old_json, new_json, &opts, format, &mut buf)
+        });
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn render_hazo_json_strs(old_json: &str, new_json: &str, options_json: &str, format: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| {
+            let format = DiffFormat::from_name(format).ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidOptions))?;
+            render_diff_json_strs(old_json, new_json, &opts, format, false, &mut buf).map(|_| ())
+        });
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn group_hazo_json_strs_by_language(old_json: &str, new_json: &str, options_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| group_json_strs_by_language(old_json, new_json, &opts, &mut buf).map(|_| ()));
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn export_hazo_json_str_to_sdd(json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = export_sdd_json_str(json, &mut buf);
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn export_hazo_changes_to_sdd(old_json: &str, new_json: &str, options_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| export_sdd_changes_json_strs(old_json, new_json, &opts, &mut buf));
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn diff_hazo_json_str_with_sdd(old_json: &str, new_sdd: &str, options_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| diff_json_str_with_sdd(old_json, new_sdd, &opts, &mut buf));
+    write_diff_result(r, &buf, out)
+}
+
+// Renvoie un objet JSON {"specs","chars","items"} contenant le texte des trois fichiers
+#[wasm_bindgen]
+pub fn export_hazo_json_str_to_delta(json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = export_delta_json_str(json).map(|files| buf.push_str(&files.to_json()));
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn export_hazo_changes_to_delta(old_json: &str, new_json: &str, options_json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = parse_json(options_json)
+        .and_then(|v| DiffOptions::from_json(&v))
+        .and_then(|opts| export_delta_changes_json_strs(old_json, new_json, &opts))
+        .map(|files| buf.push_str(&files.to_json()));
+    write_diff_result(r, &buf, out)
+}
+
+#[wasm_bindgen]
+pub fn validate_hazo_json_str(json: &str, out: &Uint8Array) -> f64 {
+    use crate::buffer::JsByteBuffer;
+    let mut buf = JsByteBuffer::new(1024);
+    let r = validate_json_str(json).map(|issues| buf.push_str(&issues_to_json(&issues)));
+    write_diff_result(r, &buf, out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,7 +602,7 @@ mod tests {
         let b = r#"{"taxons":[],"characters":[],"states":[{"id":1,"name":"B"}],"books":[]}"#;
         let mut buf = String::new();
         diff_json_strs(a, b, &mut buf).unwrap();
-        assert!(buf.contains("\"states\":{\"added\":[],\"removed\":[],\"modified\":[{\"name\":{\"old\":\"A\",\"new\":\"B\"}}]}"));
+        assert!(buf.contains("\"states\":{\"added\":[],\"removed\":[],\"modified\":[{\"index\":0,\"name\":{\"old\":\"A\",\"new\":\"B\"}}]}"));
     }
 
     #[test]
@@ -126,5 +612,431 @@ mod tests {
         let mut buf = String::new();
         let err = diff_json_strs(a, b, &mut buf);
         assert!(err.is_err());
+        let err = err.err().unwrap();
+        assert_eq!(err.property.as_deref(), Some("books"));
+        assert_eq!(err.input, Some(DiffInput::Old));
+        assert_eq!(err.to_string(), "Property missing: 'books' in old dataset");
+    }
+
+    #[test]
+    fn test_diff_json_strs_missing_collection_as_empty() {
+        let a = r#"{"taxons":[],"characters":[],"states":[]}"#;
+        let b = r#"{"taxons":[],"characters":[],"states":[],"books":[{"id":"b1"}]}"#;
+        let opts = DiffOptions { missing_collections: MissingCollectionPolicy::TreatAsEmpty, ..DiffOptions::hazo() };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.contains("\"books\":{\"added\":[{\"id\":\"b1\"}],\"removed\":[]}"));
+        let mut buf = String::new();
+        diff_json_strs_with_options(b, a, &opts, &mut buf).unwrap();
+        assert!(buf.contains("\"books\":{\"added\":[],\"removed\":[{\"id\":\"b1\"}]}"));
+    }
+
+    #[test]
+    fn test_diff_json_strs_root_properties() {
+        let a = r#"{"id":"ds1","taxons":[],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"id":"ds2","taxons":[],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.contains("\"properties\":{\"modified\":{\"id\":{\"old\":\"ds1\",\"new\":\"ds2\"}}}"));
+        assert!(!buf.contains("\"unknown\""));
+    }
+
+    #[test]
+    fn test_diff_json_strs_unknown_root_keys() {
+        let a = r#"{"taxons":[],"characters":[],"states":[],"books":[],"extra":1}"#;
+        let b = r#"{"taxons":[],"characters":[],"states":[],"books":[],"extra":1,"photos":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.contains("\"properties\":{\"added\":{\"photos\":[]}}"));
+        assert!(buf.ends_with("\"unknown\":[\"photos\"]}"));
+
+        let mut buf = String::new();
+        diff_json_strs(a, a, &mut buf).unwrap();
+        assert!(!buf.contains("\"unknown\""));
+    }
+
+    #[test]
+    fn test_diff_json_strs_custom_collections() {
+        let a = r#"{"taxons":[{"id":1}],"descriptors":[]}"#;
+        let b = r#"{"taxons":[{"id":1}],"descriptors":[{"id":2}]}"#;
+        let opts = DiffOptions {
+            collections: vec!["taxons".to_string(), "descriptors".to_string()],
+            properties: Vec::new(),
+            ..DiffOptions::hazo()
+        };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert_eq!(buf, "{\"taxons\":{\"added\":[],\"removed\":[]},\"descriptors\":{\"added\":[{\"id\":2}],\"removed\":[]}}");
+    }
+
+    #[test]
+    fn test_diff_json_strs_moved_taxon() {
+        let a = r#"{"taxons":[
+            {"id":"t0","path":[],"children":["t1","t2"]},
+            {"id":"t1","path":["t0"],"children":["t3"]},
+            {"id":"t2","path":["t0"],"children":[]},
+            {"id":"t3","path":["t0","t1"],"children":[]}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[
+            {"id":"t0","path":[],"children":["t1","t2"]},
+            {"id":"t1","path":["t0"],"children":[]},
+            {"id":"t2","path":["t0"],"children":["t3"]},
+            {"id":"t3","path":["t0","t2"],"children":[]}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.starts_with("{\"taxons\":{\"added\":[],\"removed\":[],\"moved\":[{\"id\":\"t3\",\"from\":\"t1\",\"to\":\"t2\",\"descendants\":[]}]}"));
+
+        let opts = DiffOptions { hierarchies: Vec::new(), ..DiffOptions::hazo() };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(!buf.contains("\"moved\""));
+        assert!(buf.contains("\"modified\":[{\"index\":1,\"children\""));
+    }
+
+    #[test]
+    fn test_json_strs_differ() {
+        let a = r#"{"id":"ds","taxons":[{"id":"t1","height":1.0}],"characters":[],"states":[],"books":[],"extra":1}"#;
+        let b = r#"{"id":"ds","taxons":[{"id":"t1","height":1.05}],"characters":[],"states":[],"books":[],"extra":1}"#;
+        let opts = DiffOptions::hazo();
+        assert!(!json_strs_differ(a, a, &opts).unwrap());
+        assert!(json_strs_differ(a, b, &opts).unwrap());
+        let tolerant = DiffOptions { tolerance: Some(Tolerance { absolute: 0.1, relative: 0.0 }), ..DiffOptions::hazo() };
+        assert!(!json_strs_differ(a, b, &tolerant).unwrap());
+        assert!(json_strs_differ(a, &a.replace("\"ds\"", "\"ds2\""), &opts).unwrap());
+
+        let err = json_strs_differ(a, r#"{"taxons":["#, &opts).unwrap_err();
+        assert!(err.is_parse_error());
+        assert_eq!(err.to_string(), "Invalid structure: unclosed object or array in new dataset");
+        let err = json_strs_differ(r#"{"taxons":[]}"#, a, &opts).unwrap_err();
+        assert!(err.is_property_missing());
+    }
+
+    #[test]
+    fn test_json_strs_differ_agrees_with_diff() {
+        let dataset = |taxons: &str| format!(r#"{{"id":"ds","taxons":[{}],"characters":[],"states":[],"books":[]}}"#, taxons);
+        let hazo = DiffOptions::hazo_entities();
+        let cn = DiffOptions { language: Some("cn".to_string()), ..DiffOptions::hazo_entities() };
+        let cases = [
+            (r#"{"id":"t1","path":[],"children":[]},{"id":"t2","path":[],"children":[]}"#, r#"{"id":"t2","path":[],"children":[]},{"id":"t1","path":[],"children":[]}"#, hazo.clone(), true),
+            (r#"{"id":"t1","path":[],"children":[]},{"id":"t2","path":[],"children":[]}"#, r#"{"id":"t2","path":[],"children":[]},{"id":"t1","path":[],"children":[]}"#, DiffOptions { ignore_order: true, ..hazo.clone() }, false),
+            (r#"{"id":"t1","detail":"x"}"#, r#"{"id":"t1","detail":"y"}"#, DiffOptions { ignore: vec![PathPattern::parse("/taxons/*/detail").unwrap()], ..hazo.clone() }, false),
+            (r#"{"id":"t1","height":1.0}"#, r#"{"id":"t1","height":1.05}"#, DiffOptions { tolerance: Some(Tolerance { absolute: 0.1, relative: 0.0 }), ..hazo.clone() }, false),
+            (r#"{"id":"t1","children":["a","b"]}"#, r#"{"id":"t1","children":["b","a"]}"#, DiffOptions { sets: vec![PathPattern::parse("/taxons/*/children").unwrap()], ..hazo.clone() }, false),
+            (r#"{"id":"t1","children":["a","b"]}"#, r#"{"id":"t1","children":["b","a"]}"#, DiffOptions { sequences: vec![PathPattern::parse("/taxons/*/children").unwrap()], ..hazo.clone() }, true),
+            (r#"{"id":"t1","name":"A","nameEN":"a"}"#, r#"{"id":"t1","name":"B","nameEN":"b"}"#, cn.clone(), false),
+            (r#"{"id":"t1","name":"A","nameCN":"甲"}"#, r#"{"id":"t1","name":"A","nameCN":"乙"}"#, cn.clone(), true),
+            (r#"{"id":"t1","name":"A"}"#, r#"{"id":"t1","name":"A"},{"id":"t2","name":"B"}"#, cn.clone(), false),
+            (r#"{"id":"t0","path":[],"children":["t1"]},{"id":"t1","path":["t0"],"children":[]}"#, r#"{"id":"t0","path":[],"children":[]},{"id":"t1","path":[],"children":[]}"#, hazo.clone(), true),
+            (r#"{"id":"t1","name":"A"},{"id":"t2","name":"B"}"#, r#"{"id":"t1","name":"A"},{"id":"t2","name":"C"}"#, DiffOptions::hazo(), true),
+            (r#"{"id":"t1","name":"A"},{"id":"t2","name":"B"}"#, r#"{"id":"t1","name":"A"},{"id":"t2","name":"B"}"#, DiffOptions::hazo(), false),
+        ];
+        for (old, new, opts, expected) in cases {
+            let (a, b) = (dataset(old), dataset(new));
+            let mut buf = String::new();
+            assert_eq!(json_strs_differ(&a, &b, &opts).unwrap(), expected, "{} -> {}", old, new);
+            assert_eq!(render_diff_json_strs(&a, &b, &opts, DiffFormat::Json, false, &mut buf).unwrap(), expected, "{}", buf);
+        }
+
+        // Une collection stricte est parcourue jusqu'au changement de type, même après une autre différence
+        let a = dataset(r#"{"id":"t1","name":"A"},{"id":"t2","height":12}"#);
+        let b = dataset(r#"{"id":"t1","name":"B"},{"id":"t2","height":"12"}"#);
+        let strict = DiffOptions { strict_types: vec!["taxons".to_string()], ..hazo };
+        let err = json_strs_differ(&a, &b, &strict).unwrap_err();
+        assert_eq!(err.location.as_deref(), Some("/taxons/t2/height"));
+    }
+
+    #[test]
+    fn test_diff_stats_json_strs() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t2","name":"B"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","name":"A2"},{"id":"t3","name":"C"}],"characters":[],"states":[],"books":[]}"#;
+        let stats = diff_stats_json_strs(a, b, &DiffOptions::hazo()).unwrap();
+        assert_eq!(stats.collections.len(), 1);
+        assert_eq!((stats.collections[0].added, stats.collections[0].removed, stats.collections[0].modified), (1, 1, 1));
+        assert_eq!(stats.fields, vec![("name".to_string(), 1)]);
+        assert!(stats.similarity > 0.5 && stats.similarity < 1.0);
+
+        let same = diff_stats_json_strs(a, a, &DiffOptions::hazo()).unwrap();
+        assert!(same.collections.is_empty() && !same.has_changes());
+        assert_eq!(same.similarity, 1.0);
+
+        let renamed = diff_stats_json_strs(a, &a.replace("\"books\":[]", "\"books\":[],\"id\":\"ds\""), &DiffOptions::hazo()).unwrap();
+        assert!(renamed.collections.is_empty() && renamed.has_changes());
+        assert_eq!(renamed.properties.added, 1);
+    }
+
+    #[test]
+    fn test_diff_json_strs_strict_types() {
+        let a = r#"{"taxons":[{"id":"t1","height":12}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","height":"12"}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.contains("\"oldType\":\"number\",\"newType\":\"string\""));
+
+        let opts = DiffOptions { strict_types: vec!["taxons".to_string()], ..DiffOptions::hazo_entities() };
+        let mut buf = String::new();
+        let err = diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap_err();
+        assert!(matches!(err.error_type, JsonDiffErrorType::TypeChanged));
+        assert_eq!(err.location.as_deref(), Some("/taxons/t1/height"));
+        assert!(err.property.is_none());
+        assert_eq!(err.to_string(), "Type changed in strict collection at '/taxons/t1/height'");
+
+        // Un champ nommé `oldType` n'est pas un changement de type
+        let a = r#"{"taxons":[{"id":"t1","oldType":"a"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","oldType":"b"}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.contains(r#""oldType":{"old":"a","new":"b"}"#));
+    }
+
+    #[test]
+    fn test_diff_json_strs_normalized() {
+        let a = r#"{"taxons":[{"id":"t1","name":"Cafe\u0301 ","author":"","detail":"<p>a\n  b</p>"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","name":"Café","detail":"<p>a b</p>"}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.contains("\"modified\""));
+
+        let mut opts = DiffOptions::hazo();
+        opts.normalization.enable("all");
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(!buf.contains("\"modified\""), "{}", buf);
+
+        // Les erreurs restent attribuées à leur fichier après normalisation
+        let err = json_strs_differ(a, &b.replace(",\"books\":[]", ""), &opts).unwrap_err();
+        assert!(matches!(err.input, Some(DiffInput::New)));
+        assert_eq!(err.property.as_deref(), Some("books"));
+    }
+
+    #[test]
+    fn test_diff_json_strs_moved_and_swapped_taxa() {
+        let a = r#"{"taxons":[
+            {"id":"t1","name":"Ficus","path":[],"children":[],"nameCN":"x"},
+            {"id":"t2","name":"Morus","path":[],"children":[],"detail":"y"}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[
+            {"id":"t2","name":"Morus","path":[],"children":["t1"],"detail":"y"},
+            {"id":"t1","name":"Ficus","path":["t2"],"children":[],"nameCN":"x"}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(!buf.contains("\"modified\""), "{}", buf);
+        assert!(buf.contains("{\"id\":\"t1\",\"from\":null,\"to\":\"t2\",\"descendants\":[]}"), "{}", buf);
+    }
+
+    #[test]
+    fn test_diff_json_strs_moved_with_duplicate_ids() {
+        let a = r#"{"taxons":[{"id":"t0","path":[],"children":["t1"]},{"id":"t1","path":["t0"],"children":[]},{"id":"t1","path":[],"children":[]}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t0","path":[],"children":[]},{"id":"t1","path":[],"children":[]},{"id":"t1","path":[],"children":[]}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        let diff = parse_json(&buf).unwrap();
+        let taxons = diff.get("taxons").unwrap();
+        assert!(taxons.get("moved").is_none(), "{}", buf);
+        assert!(taxons.get("modified").is_some(), "{}", buf);
+    }
+
+    #[test]
+    fn test_diff_json_strs_moved_and_reordered_taxa() {
+        let a = r#"{"taxons":[
+            {"id":"t0","path":[],"children":["t1"]},
+            {"id":"t1","path":["t0"],"children":[]},
+            {"id":"t2","path":[],"children":[]}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[
+            {"id":"t2","path":[],"children":[]},
+            {"id":"t0","path":[],"children":[]},
+            {"id":"t1","path":[],"children":[]}
+        ],"characters":[],"states":[],"books":[]}"#;
+        let opts = DiffOptions::hazo_entities();
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.starts_with(concat!(
+            "{\"taxons\":{\"added\":[],\"removed\":[],\"moved\":[",
+            "{\"id\":\"t2\",\"fromIndex\":2,\"toIndex\":0},",
+            "{\"id\":\"t1\",\"from\":\"t0\",\"to\":null,\"descendants\":[]}]}",
+        )), "{}", buf);
+
+        let opts = DiffOptions { ignore_order: true, ..opts };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(!buf.contains("fromIndex"));
+    }
+
+    #[test]
+    fn test_summarize_json_strs() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t2","name":"B"}],"characters":[],"states":[],"books":[{"id":"b2","label":"Flora"}]}"#;
+        let b = r#"{"taxons":[{"id":"t2","name":"B"},{"id":"t1","name":"A s.l."}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        summarize_json_strs(a, b, &DiffOptions::hazo(), SummaryFormat::Text, &mut buf).unwrap();
+        assert_eq!(buf, "Taxon t2 reordered from position 1 to 0\nTaxon t1 renamed from A to A s.l.\nBook b2 (Flora) removed\n");
+    }
+
+    #[test]
+    fn test_diff_json_strs_language_filter() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A","nameEN":"a","nameCN":"甲","detail":"x"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","name":"B","nameEN":"b","nameCN":"乙","detail":"y"}],"characters":[],"states":[],"books":[]}"#;
+        let opts = DiffOptions { language: Some("cn".to_string()), ..DiffOptions::hazo_entities() };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.starts_with("{\"taxons\":{\"added\":[],\"removed\":[],\"modified\":[{\"id\":\"t1\",\"nameCN\":{\"old\":\"甲\",\"new\":\"乙\"}}]}"));
+
+        let mut buf = String::new();
+        assert!(group_json_strs_by_language(a, b, &DiffOptions::hazo(), &mut buf).unwrap());
+        assert!(buf.starts_with("{\"default\":{\"taxons\":[{\"id\":\"t1\",\"field\":\"name\",\"old\":\"A\",\"new\":\"B\"}]},\"en\":"));
+
+        // Un changement des seules propriétés du jeu de données n'apparaît dans aucune langue
+        let mut buf = String::new();
+        let b = a.replace("\"books\":[]", "\"books\":[],\"id\":\"ds2\"");
+        assert!(!group_json_strs_by_language(a, &b, &DiffOptions::hazo(), &mut buf).unwrap());
+        assert_eq!(buf, "{}");
+    }
+
+    #[test]
+    fn test_diff_json_strs_language_filter_entities() {
+        let a = r#"{"id":"ds1","taxons":[{"id":"t1","name":"A","path":[],"children":[]},{"id":"t2","name":"B","path":[],"children":[]},{"id":"t3","name":"C","nameCN":"丙","path":[],"children":[]}],"characters":[],"states":[],"books":[{"id":"b1","label":"Flora"}]}"#;
+        let b = r#"{"id":"ds2","taxons":[{"id":"t2","name":"B","path":[],"children":["t1"]},{"id":"t1","name":"A","path":["t2"],"children":[]},{"id":"t4","name":"D","nameCN":"丁","detail":"x","path":[],"children":[]}],"characters":[],"states":[],"books":[]}"#;
+        let opts = DiffOptions { language: Some("cn".to_string()), ..DiffOptions::hazo_entities() };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert_eq!(buf, concat!(
+            "{\"taxons\":{\"added\":[{\"id\":\"t4\",\"nameCN\":\"丁\"}],\"removed\":[{\"id\":\"t3\",\"nameCN\":\"丙\"}]},",
+            "\"characters\":{\"added\":[],\"removed\":[]},\"states\":{\"added\":[],\"removed\":[]},\"books\":{\"added\":[],\"removed\":[]}}",
+        ));
+    }
+
+    #[test]
+    fn test_now_iso8601() {
+        let now = now_iso8601();
+        assert_eq!(now.len(), 20);
+        assert!(now.starts_with("20") && now.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_export_sdd_changes() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t2","name":"B"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t2","name":"B s.l."}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        export_sdd_changes_json_strs(a, b, &DiffOptions::hazo(), &mut buf).unwrap();
+        assert!(buf.contains("<TaxonName id=\"t2\">"));
+        assert!(!buf.contains("<TaxonName id=\"t1\">"));
+
+        let mut buf = String::new();
+        let err = export_sdd_json_str(r#"{"taxons":[{"id":"t1"}]}"#, &mut buf).err().unwrap();
+        assert_eq!(err.to_string(), "Invalid Hazo dataset");
+    }
+
+    #[test]
+    fn test_diff_json_str_with_sdd() {
+        let old = r#"{"taxons":[{"id":"t1","path":[],"name":"Ficus","detail":"","children":[],"descriptions":[]}],"characters":[],"states":[],"books":[]}"#;
+        let mut sdd = String::new();
+        export_sdd_json_str(&old.replace("Ficus", "Ficus L."), &mut sdd).unwrap();
+        let mut buf = String::new();
+        diff_json_str_with_sdd(old, &sdd, &DiffOptions::hazo(), &mut buf).unwrap();
+        assert!(buf.contains(r#""name":{"old":"Ficus","new":"Ficus L."}"#));
+        assert!(!buf.contains("detail"));
+        assert!(!buf.contains("properties"));
+
+        let mut buf = String::new();
+        let err = diff_json_str_with_sdd(old, "<Datasets>", &DiffOptions::hazo(), &mut buf).err().unwrap();
+        assert_eq!(err.to_string(), "Invalid XML document");
+    }
+
+    #[test]
+    fn test_sdd_round_trip_is_clean() {
+        let dataset = r#"{
+            "id": "flora-2024",
+            "version": 3,
+            "taxons": [
+                {"id": "t0", "name": "Acanthaceae", "nameEN": "Acanthus family", "nameCN": "爵床科", "vernacularName": "acanthes",
+                 "path": [], "children": ["t1", "t2"], "author": "Juss.", "photos": ["a.jpg"]},
+                {"id": "t1", "name": "Acanthus & co", "nameCN": "老鼠簕属", "path": ["t0"], "children": ["t3"],
+                 "detail": "<p>Herbs, \"spiny\"</p>", "descriptions": [{"descriptorId": "c1", "statesIds": ["s1", "s2"]}, {"descriptorId": "c2", "statesIds": []}]},
+                {"id": "t2", "name": "Justicia", "nameEN": "", "path": ["t0"], "children": [], "detail": "", "descriptions": []},
+                {"id": "t3", "name": "Acanthus ilicifolius", "path": ["t0", "t1"],
+                 "descriptions": [{"descriptorId": "c3", "statesIds": ["s4"], "note": "rare"}]}
+            ],
+            "characters": [
+                {"id": "c0", "name": "Leaf", "nameEN": "Leaf", "path": [], "children": ["c1", "c2"], "states": []},
+                {"id": "c1", "name": "Couleur", "nameEN": "Color", "nameCN": "颜色", "path": ["c0"], "states": ["s1", "s2"], "inherentStateId": "s1"},
+                {"id": "c2", "name": "Marge", "path": ["c0"], "detail": "Bord du limbe", "states": ["s3"], "inapplicableStatesIds": ["s1"]},
+                {"id": "c3", "name": "Fleur", "states": ["s4"]}
+            ],
+            "states": [
+                {"id": "s1", "name": "Rouge", "nameEN": "Red", "nameCN": "红"},
+                {"id": "s2", "name": "Bleu", "description": "Bleu franc"},
+                {"id": "s3", "name": "Dentée", "nameCN": ""},
+                {"id": "s4", "name": "Solitaire"}
+            ],
+            "books": [{"id": "b1", "label": "Flora of China", "year": 2011}]
+        }"#;
+        let mut sdd = String::new();
+        export_sdd_json_str(dataset, &mut sdd).unwrap();
+        assert!(sdd.contains("<Label xml:lang=\"zh\">爵床科</Label>"));
+        let mut buf = String::new();
+        diff_json_str_with_sdd(dataset, &sdd, &DiffOptions::hazo(), &mut buf).unwrap();
+        assert_eq!(buf, concat!(
+            "{\"taxons\":{\"added\":[],\"removed\":[]},\"characters\":{\"added\":[],\"removed\":[]},",
+            "\"states\":{\"added\":[],\"removed\":[]},\"books\":{\"added\":[],\"removed\":[]}}",
+        ));
+
+        let edited = sdd.replace("<Label xml:lang=\"zh\">红</Label>", "<Label xml:lang=\"zh\">紅</Label>");
+        let mut buf = String::new();
+        diff_json_str_with_sdd(dataset, &edited, &DiffOptions::hazo_entities(), &mut buf).unwrap();
+        assert!(buf.contains(r#""states":{"added":[],"removed":[],"modified":[{"id":"s1","nameCN":{"old":"红","new":"紅"}}]}"#), "{}", buf);
+    }
+
+    #[test]
+    fn test_export_delta_changes() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A"},{"id":"t2","name":"B"}],"characters":[{"id":"c1","name":"Color","states":["s1"]}],"states":[{"id":"s1","name":"Red"}],"books":[]}"#;
+        let b = a.replace(r#""name":"B""#, r#""name":"B s.l.""#);
+        let files = export_delta_changes_json_strs(a, &b, &DiffOptions::hazo()).unwrap();
+        assert!(files.items.contains("# B s.l. <t2>/"));
+        assert!(!files.items.contains("<t1>"));
+        assert!(files.chars.contains("#1. Color <c1>/"));
+
+        let files = export_delta_json_str(a).unwrap();
+        assert!(files.items.contains("<t1>") && files.items.contains("<t2>"));
+    }
+
+    #[test]
+    fn test_render_diff_json_strs_text() {
+        let a = r#"{"taxons":[{"id":"t1","name":"A","detail":"x"}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","name":"A","detail":"y"}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        render_diff_json_strs(a, b, &DiffOptions::hazo(), DiffFormat::Text, false, &mut buf).unwrap();
+        assert_eq!(buf, "Taxa\n  ~ Taxon t1 (A)\n    detail\n      - \"x\"\n      + \"y\"\n");
+
+        let mut buf = String::new();
+        assert!(!render_diff_json_strs(a, a, &DiffOptions::hazo(), DiffFormat::Text, false, &mut buf).unwrap());
+        assert!(buf.is_empty());
+
+        let mut buf = String::new();
+        assert!(render_diff_json_strs(a, b, &DiffOptions::hazo(), DiffFormat::Html, false, &mut buf).unwrap());
+        assert!(buf.contains("<summary>~ Taxon t1 (A)</summary>"));
+
+        let mut buf = String::new();
+        render_diff_json_strs(a, b, &DiffOptions::hazo(), DiffFormat::Markdown, false, &mut buf).unwrap();
+        assert_eq!(buf, "## Taxa\n\n1 modified.\n\n### Modified\n\n- A (`t1`): detail\n");
+    }
+
+    #[test]
+    fn test_diff_json_strs_sequences() {
+        let a = r#"{"taxons":[{"id":"t1","children":["t2","t3","t4"]}],"characters":[],"states":[],"books":[]}"#;
+        let b = r#"{"taxons":[{"id":"t1","children":["t2","t5","t3","t4"]}],"characters":[],"states":[],"books":[]}"#;
+        let mut buf = String::new();
+        diff_json_strs(a, b, &mut buf).unwrap();
+        assert!(buf.contains(r#""children":{"added":["t4"],"removed":[],"modified":[{"old":"t3","new":"t5"},{"old":"t4","new":"t3"}]}"#));
+
+        let opts = DiffOptions { sequences: vec![PathPattern::parse("/taxons/*/children").unwrap()], ..DiffOptions::hazo() };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.contains(r#""children":{"inserted":[{"index":1,"value":"t5"}],"deleted":[]}"#));
+
+        let opts = DiffOptions { identity_key: Some("id".to_string()), ..opts };
+        let mut buf = String::new();
+        diff_json_strs_with_options(a, b, &opts, &mut buf).unwrap();
+        assert!(buf.contains(r#""modified":[{"id":"t1","children":{"inserted":[{"index":1,"value":"t5"}],"deleted":[]}}]"#), "{}", buf);
     }
 }
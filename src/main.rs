@@ -1,27 +1,429 @@
-use hazojsondiff::diff_json_strs;
+use hazojsondiff::{
+    diff_stats_json_strs, export_delta_changes_json_strs, export_delta_json_str, export_sdd_changes_json_strs, export_sdd_json_str, group_json_strs_by_language, issues_to_json, issues_to_text, json_strs_differ, sdd_to_hazo_json, sdd_view_json_str, summarize_json_strs, validate_json_str, DiffInput, DiffOptions,
+    render_diff_json_strs, DiffFormat, FuzzyMatch, JsonDiffError, MissingCollectionPolicy, PathPattern, SummaryFormat, TextDiff, TextGranularity, Tolerance,
+};
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 3 {
-        eprintln!("Usage: {} <old_dataset.json> <new_dataset.json>", args[0]);
-        std::process::exit(1);
+// Codes de sortie de diff(1) : 0 si identiques, 1 si différents, 2 en cas d'erreur ;
+// `validate` suit la même convention, 1 signalant des problèmes d'intégrité
+const EXIT_DIFFERENT: i32 = 1;
+const EXIT_TROUBLE: i32 = 2;
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {} [--collections <name,...>] [--missing-collections strict|empty] [--hierarchies <name,...>] [--identity-key <key>] [--language <code>] [--ignore-order] [--detect-renames] [--fuzzy-match <threshold>] [--fuzzy-weight <field>=<w>]... [--sequence <pointer>]... [--set <pointer>]... [--strict-types <name,...>] [--normalize trim,unicode,whitespace,empty|all] [--case-fold <pointer>]... [--ignore <pointer>]... [--abs-tolerance [<pointer>=]<x>]... [--rel-tolerance [<pointer>=]<x>]... [--only <pointer>]... [--text-diff words|lines] [--text-diff-min-length <n>] [--group-by-language] [--new-sdd] [--stat] [--quiet] [--format json|text|html|markdown] [--color auto|always|never] <old_dataset.json> <new_dataset.json|new_dataset.sdd.xml>", program);
+    eprintln!("       {} summary [diff options] [--format text|markdown|json] <old_dataset.json> <new_dataset.json>", program);
+    eprintln!("       {} validate [--format text|json] <dataset.json>", program);
+    eprintln!("       {} sdd [diff options] [--since <old_dataset.json>] <dataset.json>", program);
+    eprintln!("       {} delta [diff options] [--since <old_dataset.json>] [--out-dir <dir>] <dataset.json>", program);
+    eprintln!("Exit status is 0 when the datasets are identical (or valid), 1 when they differ (or have issues), 2 on error.");
+    std::process::exit(EXIT_TROUBLE);
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string()).collect()
+}
+
+fn read_file(path: &str, what: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", what, e);
+        std::process::exit(EXIT_TROUBLE);
+    })
+}
+
+// Un fichier mal formé ou une propriété manquante sont signalés comme tels, le reste sous `action`
+fn fail(action: &str, e: &JsonDiffError) -> ! {
+    let input = e.input.map_or("input".to_string(), |i| i.to_string());
+    if e.is_parse_error() {
+        eprintln!("Failed to parse {}: {}", input, e.error_type);
+    } else if e.is_property_missing() {
+        eprintln!("Missing property '{}' in {}", e.property.as_deref().unwrap_or("?"), input);
+    } else {
+        eprintln!("{}: {}", action, e);
     }
+    std::process::exit(EXIT_TROUBLE);
+}
+
+// `<x>` règle la tolérance globale, `<pointer>=<x>` celle d'un chemin
+fn set_tolerance(opts: &mut DiffOptions, spec: &str, relative: bool) -> Option<()> {
+    let (pointer, value) = match spec.rsplit_once('=') {
+        Some((pointer, value)) => (Some(pointer), value),
+        None => (None, spec),
+    };
+    let value: f64 = value.parse().ok().filter(|v: &f64| *v >= 0.0)?;
+    let tolerance = match pointer {
+        Some(pointer) => {
+            let pattern = PathPattern::parse(pointer).ok()?;
+            match opts.tolerances.iter().position(|(p, _)| *p == pattern) {
+                Some(i) => &mut opts.tolerances[i].1,
+                None => {
+                    opts.tolerances.push((pattern, Tolerance::default()));
+                    &mut opts.tolerances.last_mut()?.1
+                }
+            }
+        }
+        None => opts.tolerance.get_or_insert_with(Tolerance::default),
+    };
+    if relative { tolerance.relative = value } else { tolerance.absolute = value }
+    Some(())
+}
 
-    let old_json = std::fs::read_to_string(&args[1]).unwrap_or_else(|e| {
-        eprintln!("Failed to read old dataset: {}", e);
-        std::process::exit(1);
-    });
-    let new_json = std::fs::read_to_string(&args[2]).unwrap_or_else(|e| {
-        eprintln!("Failed to read new dataset: {}", e);
-        std::process::exit(1);
-    });
+// Options communes aux commandes qui calculent un diff ; renvoie false si l'argument n'en fait pas partie
+fn parse_diff_flag(program: &str, args: &[String], i: &mut usize, opts: &mut DiffOptions) -> bool {
+    match args[*i].as_str() {
+        "--collections" => {
+            *i += 1;
+            let value = args.get(*i).unwrap_or_else(|| usage(program));
+            opts.collections = split_list(value);
+        }
+        "--hierarchies" => {
+            *i += 1;
+            let value = args.get(*i).unwrap_or_else(|| usage(program));
+            opts.hierarchies = split_list(value);
+        }
+        "--identity-key" => {
+            *i += 1;
+            opts.identity_key = Some(args.get(*i).unwrap_or_else(|| usage(program)).clone());
+        }
+        "--language" => {
+            *i += 1;
+            let code = args.get(*i).unwrap_or_else(|| usage(program));
+            if !opts.languages.iter().any(|l| &l.code == code) {
+                usage(program);
+            }
+            opts.language = Some(code.clone());
+        }
+        "--ignore-order" => opts.ignore_order = true,
+        "--detect-renames" => opts.detect_renames = true,
+        "--fuzzy-match" => {
+            *i += 1;
+            let threshold = args.get(*i).and_then(|v| v.parse::<f64>().ok()).filter(|t| (0.0..=1.0).contains(t)).unwrap_or_else(|| usage(program));
+            opts.fuzzy_match.get_or_insert_with(|| FuzzyMatch::new(threshold)).threshold = threshold;
+        }
+        "--fuzzy-weight" => {
+            *i += 1;
+            let (field, weight) = args.get(*i)
+                .and_then(|v| v.rsplit_once('='))
+                .and_then(|(field, w)| Some((field.to_string(), w.parse::<f64>().ok().filter(|w| *w >= 0.0)?)))
+                .unwrap_or_else(|| usage(program));
+            opts.fuzzy_match.get_or_insert_with(|| FuzzyMatch::new(FuzzyMatch::DEFAULT_THRESHOLD)).weights.push((field, weight));
+        }
+        "--sequence" => {
+            *i += 1;
+            let pattern = args.get(*i).and_then(|v| PathPattern::parse(v).ok()).unwrap_or_else(|| usage(program));
+            opts.sequences.push(pattern);
+        }
+        "--set" => {
+            *i += 1;
+            let pattern = args.get(*i).and_then(|v| PathPattern::parse(v).ok()).unwrap_or_else(|| usage(program));
+            opts.sets.push(pattern);
+        }
+        "--abs-tolerance" | "--rel-tolerance" => {
+            let relative = args[*i] == "--rel-tolerance";
+            *i += 1;
+            let spec = args.get(*i).unwrap_or_else(|| usage(program));
+            set_tolerance(opts, spec, relative).unwrap_or_else(|| usage(program));
+        }
+        "--strict-types" => {
+            *i += 1;
+            opts.strict_types = split_list(args.get(*i).unwrap_or_else(|| usage(program)));
+        }
+        "--normalize" => {
+            *i += 1;
+            let steps = args.get(*i).unwrap_or_else(|| usage(program));
+            if !steps.split(',').all(|step| opts.normalization.enable(step)) {
+                usage(program);
+            }
+        }
+        "--case-fold" => {
+            *i += 1;
+            let pattern = args.get(*i).and_then(|v| PathPattern::parse(v).ok()).unwrap_or_else(|| usage(program));
+            opts.normalization.case_fold.push(pattern);
+        }
+        "--ignore" | "--only" => {
+            let flag = args[*i].as_str();
+            *i += 1;
+            let pattern = args.get(*i).and_then(|v| PathPattern::parse(v).ok()).unwrap_or_else(|| usage(program));
+            if flag == "--ignore" { opts.ignore.push(pattern) } else { opts.only.push(pattern) }
+        }
+        "--text-diff" => {
+            *i += 1;
+            let granularity = args.get(*i).and_then(|v| TextGranularity::from_name(v)).unwrap_or_else(|| usage(program));
+            let min_length = opts.text_diff.map_or(TextDiff::DEFAULT_MIN_LENGTH, |t| t.min_length);
+            opts.text_diff = Some(TextDiff { granularity, min_length });
+        }
+        "--text-diff-min-length" => {
+            *i += 1;
+            let min_length = args.get(*i).and_then(|v| v.parse().ok()).unwrap_or_else(|| usage(program));
+            let granularity = opts.text_diff.map_or(TextGranularity::Words, |t| t.granularity);
+            opts.text_diff = Some(TextDiff { granularity, min_length });
+        }
+        "--missing-collections" => {
+            *i += 1;
+            opts.missing_collections = args.get(*i)
+                .and_then(|v| MissingCollectionPolicy::from_name(v))
+                .unwrap_or_else(|| usage(program));
+        }
+        _ => return false,
+    }
+    true
+}
+
+// NO_COLOR (https://no-color.org) désactive la couleur, sauf si elle est explicitement demandée
+fn use_color(choice: &str) -> bool {
+    use std::io::IsTerminal;
+    match choice {
+        "always" => true,
+        "never" => false,
+        _ => std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal(),
+    }
+}
+
+fn run_diff(program: &str, args: &[String]) {
+    let mut opts = DiffOptions::hazo();
+    let mut group_by_language = false;
+    let mut new_sdd = false;
+    let mut stat = false;
+    let mut quiet = false;
+    let mut format = DiffFormat::Json;
+    let mut color = "auto";
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if !parse_diff_flag(program, args, &mut i, &mut opts) {
+            match args[i].as_str() {
+                "--group-by-language" => group_by_language = true,
+                "--new-sdd" => new_sdd = true,
+                "--stat" => stat = true,
+                "--quiet" | "-q" => quiet = true,
+                "--format" => {
+                    i += 1;
+                    format = args.get(i).and_then(|v| DiffFormat::from_name(v)).unwrap_or_else(|| usage(program));
+                }
+                "--color" => {
+                    i += 1;
+                    color = match args.get(i).map(|s| s.as_str()) {
+                        Some(choice @ ("auto" | "always" | "never")) => choice,
+                        _ => usage(program),
+                    };
+                }
+                arg if arg.starts_with("--") => usage(program),
+                arg => files.push(arg),
+            }
+        }
+        i += 1;
+    }
+    if files.len() != 2 {
+        usage(program);
+    }
+
+    if stat && group_by_language {
+        usage(program);
+    }
+
+    let mut old_json = read_file(files[0], "old dataset");
+    let mut new_json = read_file(files[1], "new dataset");
+    if new_sdd {
+        new_json = sdd_to_hazo_json(&new_json).unwrap_or_else(|e| fail("Failed to convert SDD document", &e.in_input(DiffInput::New)));
+        old_json = sdd_view_json_str(&old_json).unwrap_or_else(|e| fail("Failed to convert old dataset", &e.in_input(DiffInput::Old)));
+    }
 
+    let action = "Failed to diff datasets";
+    if quiet {
+        // Le regroupement par langue écarte les propriétés du jeu de données : il décide seul du code de sortie
+        let r = if group_by_language {
+            group_json_strs_by_language(&old_json, &new_json, &opts, &mut String::new())
+        } else {
+            json_strs_differ(&old_json, &new_json, &opts)
+        };
+        let differ = r.unwrap_or_else(|e| fail(action, &e));
+        std::process::exit(if differ { EXIT_DIFFERENT } else { 0 });
+    }
+
+    let differ = if stat {
+        let stats = diff_stats_json_strs(&old_json, &new_json, &opts).unwrap_or_else(|e| fail(action, &e));
+        print!("{}", stats.to_text());
+        stats.has_changes()
+    } else {
+        let mut buf = String::new();
+        let r = if group_by_language {
+            group_json_strs_by_language(&old_json, &new_json, &opts, &mut buf)
+        } else {
+            render_diff_json_strs(&old_json, &new_json, &opts, format, use_color(color), &mut buf)
+        };
+        let differ = r.unwrap_or_else(|e| fail(action, &e));
+        if format == DiffFormat::Json || group_by_language {
+            println!("{}", buf);
+        } else {
+            print!("{}", buf);
+        }
+        differ
+    };
+    if differ {
+        std::process::exit(EXIT_DIFFERENT);
+    }
+}
+
+fn run_summary(program: &str, args: &[String]) {
+    let mut opts = DiffOptions::hazo();
+    let mut format = SummaryFormat::Text;
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if !parse_diff_flag(program, args, &mut i, &mut opts) {
+            match args[i].as_str() {
+                "--format" => {
+                    i += 1;
+                    format = args.get(i).and_then(|v| SummaryFormat::from_name(v)).unwrap_or_else(|| usage(program));
+                }
+                arg if arg.starts_with("--") => usage(program),
+                arg => files.push(arg),
+            }
+        }
+        i += 1;
+    }
+    if files.len() != 2 {
+        usage(program);
+    }
+
+    let old_json = read_file(files[0], "old dataset");
+    let new_json = read_file(files[1], "new dataset");
+
+    let mut buf = String::new();
+    match summarize_json_strs(&old_json, &new_json, &opts, format, &mut buf) {
+        Ok(()) if format == SummaryFormat::Json => println!("{}", buf),
+        Ok(()) => print!("{}", buf),
+        Err(e) => fail("Failed to summarize datasets", &e),
+    }
+}
+
+fn run_validate(program: &str, args: &[String]) {
+    let mut json_output = false;
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" => {
+                i += 1;
+                json_output = match args.get(i).map(|s| s.as_str()) {
+                    Some("json") => true,
+                    Some("text") => false,
+                    _ => usage(program),
+                };
+            }
+            arg if arg.starts_with("--") => usage(program),
+            arg => files.push(arg),
+        }
+        i += 1;
+    }
+    if files.len() != 1 {
+        usage(program);
+    }
+
+    let json = read_file(files[0], "dataset");
+    match validate_json_str(&json) {
+        Ok(issues) => {
+            if json_output {
+                println!("{}", issues_to_json(&issues));
+            } else {
+                print!("{}", issues_to_text(&issues));
+            }
+            if !issues.is_empty() {
+                std::process::exit(EXIT_DIFFERENT);
+            }
+        }
+        Err(e) => fail("Failed to validate dataset", &e),
+    }
+}
+
+fn run_sdd(program: &str, args: &[String]) {
+    let mut opts = DiffOptions::hazo();
+    let mut since = None;
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if !parse_diff_flag(program, args, &mut i, &mut opts) {
+            match args[i].as_str() {
+                "--since" => {
+                    i += 1;
+                    since = Some(args.get(i).unwrap_or_else(|| usage(program)).as_str());
+                }
+                arg if arg.starts_with("--") => usage(program),
+                arg => files.push(arg),
+            }
+        }
+        i += 1;
+    }
+    if files.len() != 1 {
+        usage(program);
+    }
+
+    let json = read_file(files[0], "dataset");
     let mut buf = String::new();
-    match diff_json_strs(&old_json, &new_json, &mut buf) {
-        Ok(()) => println!("{}", buf),
-        Err(e) => {
-            eprintln!("Failed to diff datasets: {}", e);
-            std::process::exit(1);
+    let r = match since {
+        Some(path) => export_sdd_changes_json_strs(&read_file(path, "old dataset"), &json, &opts, &mut buf),
+        None => export_sdd_json_str(&json, &mut buf),
+    };
+    match r {
+        Ok(()) => print!("{}", buf),
+        Err(e) => fail("Failed to export dataset", &e),
+    }
+}
+
+fn run_delta(program: &str, args: &[String]) {
+    let mut opts = DiffOptions::hazo();
+    let mut since = None;
+    let mut out_dir = ".";
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if !parse_diff_flag(program, args, &mut i, &mut opts) {
+            match args[i].as_str() {
+                "--since" => {
+                    i += 1;
+                    since = Some(args.get(i).unwrap_or_else(|| usage(program)).as_str());
+                }
+                "--out-dir" => {
+                    i += 1;
+                    out_dir = args.get(i).unwrap_or_else(|| usage(program));
+                }
+                arg if arg.starts_with("--") => usage(program),
+                arg => files.push(arg),
+            }
         }
+        i += 1;
+    }
+    if files.len() != 1 {
+        usage(program);
+    }
+
+    let json = read_file(files[0], "dataset");
+    let r = match since {
+        Some(path) => export_delta_changes_json_strs(&read_file(path, "old dataset"), &json, &opts),
+        None => export_delta_json_str(&json),
+    };
+    let delta = r.unwrap_or_else(|e| fail("Failed to export dataset", &e));
+    // Avec --since seul le fichier items change
+    let outputs = if since.is_some() {
+        vec![("items", &delta.items)]
+    } else {
+        vec![("specs", &delta.specs), ("chars", &delta.chars), ("items", &delta.items)]
+    };
+    for (name, content) in outputs {
+        let path = std::path::Path::new(out_dir).join(name);
+        if let Err(e) = std::fs::write(&path, content) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            std::process::exit(EXIT_TROUBLE);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("validate") => run_validate(&args[0], &args[2..]),
+        Some("delta") => run_delta(&args[0], &args[2..]),
+        Some("sdd") => run_sdd(&args[0], &args[2..]),
+        Some("summary") => run_summary(&args[0], &args[2..]),
+        Some("diff") => run_diff(&args[0], &args[2..]),
+        _ => run_diff(&args[0], &args[1..]),
     }
-}
\ No newline at end of file
+}
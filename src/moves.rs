@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use crate::json::JsonValue;
+use crate::jsondiff::json_value_to_string;
+
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Move<'a> {
+    pub id: &'a str,
+    pub from: Option<&'a str>,
+    pub to: Option<&'a str>,
+    pub descendants: Vec<&'a str>,
+}
+
+impl std::fmt::Display for Move<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} moved from {} to {}", self.id, self.from.unwrap_or("the root"), self.to.unwrap_or("the root"))?;
+        match self.descendants.len() {
+            0 => Ok(()),
+            1 => write!(f, " with 1 descendant"),
+            n => write!(f, " with {} descendants", n),
+        }
+    }
+}
+
+struct Node<'v, 'a> {
+    index: usize,
+    path: Vec<&'a str>,
+    children: &'v [JsonValue<'a>],
+}
+
+fn index_nodes<'v, 'a>(items: &'v [JsonValue<'a>]) -> HashMap<&'a str, Node<'v, 'a>> {
+    let mut nodes = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let Some(id) = item.get("id").and_then(|v| v.as_str()) else { continue };
+        let path = item.get("path").and_then(|v| v.as_array()).unwrap_or(&[]).iter().filter_map(|v| v.as_str()).collect();
+        let children = item.get("children").and_then(|v| v.as_array()).unwrap_or(&[]);
+        nodes.insert(id, Node { index, path, children });
+    }
+    nodes
+}
+
+// Un élément est déplacé quand son parent direct (dernier élément de `path`) change ;
+// ses descendants qui le suivent ne sont pas des déplacements à part entière.
+pub fn detect_moves<'a>(old: &[JsonValue<'a>], new: &[JsonValue<'a>]) -> Vec<Move<'a>> {
+    let old_nodes = index_nodes(old);
+    let new_nodes = index_nodes(new);
+    // Descendants de chaque ancêtre dans l'ancienne collection, dans son ordre
+    let mut old_descendants: HashMap<&str, Vec<&str>> = HashMap::new();
+    for d in old.iter().filter_map(|v| v.get("id").and_then(|v| v.as_str())) {
+        let path = &old_nodes[d].path;
+        for (k, ancestor) in path.iter().enumerate() {
+            if !path[..k].contains(ancestor) {
+                old_descendants.entry(ancestor).or_default().push(d);
+            }
+        }
+    }
+    let mut moves = Vec::new();
+    for item in new {
+        let Some(id) = item.get("id").and_then(|v| v.as_str()) else { continue };
+        let (Some(old_node), Some(new_node)) = (old_nodes.get(id), new_nodes.get(id)) else { continue };
+        let from = old_node.path.last().copied();
+        let to = new_node.path.last().copied();
+        if from == to {
+            continue;
+        }
+        let descendants = old_descendants
+            .get(id)
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .copied()
+            .filter(|d| new_nodes.get(d).is_some_and(|n| n.path.contains(&id)))
+            .collect();
+        moves.push(Move { id, from, to, descendants });
+    }
+    moves
+}
+
+// Copie de l'ancienne collection où les chemins et listes d'enfants sont déjà mis à jour
+// pour les déplacements, afin que le diff ne rapporte que les autres modifications.
+pub fn mask_moves<'a>(old: &[JsonValue<'a>], new: &[JsonValue<'a>], moves: &[Move<'a>]) -> Vec<JsonValue<'a>> {
+    let mut masked = old.to_vec();
+    let old_nodes = index_nodes(old);
+    let new_nodes = index_nodes(new);
+    for m in moves {
+        for id in std::iter::once(&m.id).chain(m.descendants.iter()) {
+            if let (Some(o), Some(n)) = (old_nodes.get(id), new_nodes.get(id))
+                && let Some(path) = new[n.index].get("path") {
+                set_field(&mut masked[o.index], "path", path.clone());
+            }
+        }
+        if let Some(from) = m.from.filter(|f| new_nodes.contains_key(f)).and_then(|f| old_nodes.get(f)) {
+            let children = children_of(&masked[from.index], from.children);
+            let children = children.into_iter().filter(|c| c.as_str() != Some(m.id)).collect();
+            set_field(&mut masked[from.index], "children", JsonValue::Array(children));
+        }
+        if let (Some(to_old), Some(to_new)) = (m.to.and_then(|t| old_nodes.get(t)), m.to.and_then(|t| new_nodes.get(t))) {
+            let mut children = children_of(&masked[to_old.index], to_old.children);
+            if !children.iter().any(|c| c.as_str() == Some(m.id)) {
+                let pos = to_new.children.iter().position(|c| c.as_str() == Some(m.id)).unwrap_or(children.len());
+                children.insert(pos.min(children.len()), JsonValue::String(m.id));
+            }
+            set_field(&mut masked[to_old.index], "children", JsonValue::Array(children));
+        }
+    }
+    masked
+}
+
+fn children_of<'a>(item: &JsonValue<'a>, fallback: &[JsonValue<'a>]) -> Vec<JsonValue<'a>> {
+    item.get("children").and_then(|v| v.as_array()).unwrap_or(fallback).to_vec()
+}
+
+fn set_field<'a>(item: &mut JsonValue<'a>, key: &'a str, value: JsonValue<'a>) {
+    if let JsonValue::Object(fields) = item {
+        match fields.iter_mut().find(|(k, _)| *k == key) {
+            Some(field) => field.1 = value,
+            None => fields.push((key, value)),
+        }
+    }
+}
+
+impl Move<'_> {
+    pub fn to_json(&self) -> String {
+        let id_or_null = |id: Option<&str>| id.map_or("null".to_string(), |id| json_value_to_string(&JsonValue::String(id)));
+        let mut s = String::from("{\"id\":");
+        s.push_str(&json_value_to_string(&JsonValue::String(self.id)));
+        s.push_str(",\"from\":");
+        s.push_str(&id_or_null(self.from));
+        s.push_str(",\"to\":");
+        s.push_str(&id_or_null(self.to));
+        s.push_str(",\"descendants\":");
+        s.push_str(&json_value_to_string(&JsonValue::Array(self.descendants.iter().map(|d| JsonValue::String(d)).collect())));
+        s.push('}');
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::jsondiff::diff_json_value;
+
+    const OLD: &str = r#"[
+        {"id":"t0","path":[],"children":["t1","t2"]},
+        {"id":"t1","path":["t0"],"children":["t3"]},
+        {"id":"t2","path":["t0"],"children":[]},
+        {"id":"t3","path":["t0","t1"],"children":["t4"]},
+        {"id":"t4","path":["t0","t1","t3"],"children":[]}
+    ]"#;
+    const NEW: &str = r#"[
+        {"id":"t0","path":[],"children":["t1","t2"]},
+        {"id":"t1","path":["t0"],"children":[]},
+        {"id":"t2","path":["t0"],"children":["t3"]},
+        {"id":"t3","path":["t0","t2"],"children":["t4"]},
+        {"id":"t4","path":["t0","t2","t3"],"children":[]}
+    ]"#;
+
+    #[test]
+    fn test_detect_move_with_descendants() {
+        let old = parse_json(OLD).unwrap();
+        let new = parse_json(NEW).unwrap();
+        let moves = detect_moves(old.as_array().unwrap(), new.as_array().unwrap());
+        assert_eq!(moves, vec![Move { id: "t3", from: Some("t1"), to: Some("t2"), descendants: vec!["t4"] }]);
+        assert_eq!(moves[0].to_string(), "t3 moved from t1 to t2 with 1 descendant");
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].to_json(), "{\"id\":\"t3\",\"from\":\"t1\",\"to\":\"t2\",\"descendants\":[\"t4\"]}");
+    }
+
+    #[test]
+    fn test_mask_moves_hides_hierarchy_changes() {
+        let old = parse_json(OLD).unwrap();
+        let new_json = NEW.replace(r#""id":"t4","#, r#""id":"t4","name":"x","#);
+        let new = parse_json(&new_json).unwrap();
+        let (old, new) = (old.as_array().unwrap(), new.as_array().unwrap());
+        let moves = detect_moves(old, new);
+        let masked = JsonValue::Array(mask_moves(old, new, &moves));
+        let mut buf = String::new();
+        diff_json_value(&masked, &JsonValue::Array(new.to_vec()), true, false, &mut buf);
+        assert_eq!(buf, "{\"added\":[],\"removed\":[],\"modified\":[{\"index\":4,\"added\":{\"name\":\"x\"}}]}");
+    }
+
+    #[test]
+    fn test_no_move() {
+        let old = parse_json(OLD).unwrap();
+        let moves = detect_moves(old.as_array().unwrap(), old.as_array().unwrap());
+        assert!(moves.is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::json::{escape, unescape, JsonValue};
+use crate::jsondiff::json_value_to_string;
+use crate::options::Normalization;
+use crate::paths::JsonPath;
+use crate::unicode::nfc;
+
+fn normalize_text(raw: &str, n: &Normalization, path: &JsonPath) -> String {
+    let mut text = unescape(raw).into_owned();
+    if n.unicode {
+        text = nfc(&text);
+    }
+    if n.collapse_whitespace {
+        text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    } else if n.trim {
+        text = text.trim().to_string();
+    }
+    if n.folds_case(path) {
+        text = text.to_lowercase();
+    }
+    text
+}
+
+// Textes normalisés des chaînes de `value` dans l'ordre du parcours, déjà échappés
+pub fn normalized_strings(value: &JsonValue, n: &Normalization) -> Vec<String> {
+    let mut strings = Vec::new();
+    collect_strings(value, n, &mut JsonPath::root(), &mut strings);
+    strings
+}
+
+fn collect_strings(v: &JsonValue, n: &Normalization, path: &mut JsonPath, out: &mut Vec<String>) {
+    match v {
+        JsonValue::String(s) => {
+            let quoted = escape(&normalize_text(s, n, path));
+            out.push(quoted[1..quoted.len() - 1].to_string());
+        }
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                path.push_index(i);
+                collect_strings(item, n, path, out);
+                path.pop();
+            }
+        }
+        JsonValue::Object(fields) => {
+            for (k, item) in fields {
+                path.push(k);
+                collect_strings(item, n, path, out);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+// Valeur normalisée dont les chaînes pointent dans `strings`, tiré de `normalized_strings` sur la
+// même valeur (les chaînes de `JsonValue` pointent dans le texte source et ne peuvent pas être
+// réécrites sur place)
+pub fn normalize_value<'s>(value: &JsonValue<'s>, n: &Normalization, strings: &'s [String]) -> JsonValue<'s> {
+    rebuild(value, n, &mut strings.iter(), true)
+}
+
+fn rebuild<'s>(v: &JsonValue<'s>, n: &Normalization, strings: &mut std::slice::Iter<'s, String>, root: bool) -> JsonValue<'s> {
+    match v {
+        JsonValue::String(_) => JsonValue::String(strings.next().map_or("", String::as_str)),
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(|item| rebuild(item, n, strings, false)).collect()),
+        JsonValue::Object(fields) => {
+            // Les clés racine décrivent la structure du jeu de données : elles restent telles quelles
+            let drops_empty = n.empty_as_missing && !root;
+            let mut kept = Vec::with_capacity(fields.len());
+            for (k, item) in fields {
+                let item = rebuild(item, n, strings, false);
+                if !(drops_empty && matches!(item, JsonValue::Null | JsonValue::String(""))) {
+                    kept.push((*k, item));
+                }
+            }
+            JsonValue::Object(kept)
+        }
+        other => other.clone(),
+    }
+}
+
+// Texte JSON normalisé
+pub fn normalize_json(value: &JsonValue, n: &Normalization) -> String {
+    let strings = normalized_strings(value, n);
+    json_value_to_string(&normalize_value(value, n, &strings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::paths::PathPattern;
+
+    #[test]
+    fn test_normalize_json() {
+        let value = parse_json(r#"{"taxons":[{"id":"t1","name":" Café  ","detail":"<p>a\n   b</p>","author":"","note":null,"code":"ABC"}],"books":null}"#).unwrap();
+        let n = Normalization {
+            trim: true,
+            unicode: true,
+            collapse_whitespace: true,
+            empty_as_missing: true,
+            case_fold: vec![PathPattern::parse("/taxons/*/code").unwrap()],
+        };
+        assert_eq!(normalize_json(&value, &n), r#"{"taxons":[{"id":"t1","name":"Café","detail":"<p>a b</p>","code":"abc"}],"books":null}"#);
+
+        let trim_only = Normalization { trim: true, ..Normalization::default() };
+        assert_eq!(normalize_json(&parse_json(r#"[" a  b "]"#).unwrap(), &trim_only), r#"["a  b"]"#);
+    }
+
+    #[test]
+    fn test_normalize_mark_order() {
+        let unicode = Normalization { unicode: true, ..Normalization::default() };
+        let forms: Vec<String> = [r#"["e\u0302\u0323"]"#, r#"["e\u0323\u0302"]"#, r#"["\u1ec7"]"#]
+            .iter()
+            .map(|s| normalize_json(&parse_json(s).unwrap(), &unicode))
+            .collect();
+        assert!(forms.iter().all(|f| *f == forms[2]), "{:?}", forms);
+    }
+}
@@ -0,0 +1,601 @@
+use crate::errors::{JsonDiffError, JsonDiffErrorType};
+use crate::json::JsonValue;
+use crate::paths::{pattern_list, JsonPath, PathPattern};
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum MissingCollectionPolicy {
+    Strict,
+    TreatAsEmpty,
+}
+
+impl MissingCollectionPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "strict" => Some(MissingCollectionPolicy::Strict),
+            "empty" => Some(MissingCollectionPolicy::TreatAsEmpty),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Language {
+    pub code: String,
+    pub fields: Vec<String>,
+}
+
+impl Language {
+    pub fn new(code: &str, fields: &[&str]) -> Self {
+        Language { code: code.to_string(), fields: fields.iter().map(|f| f.to_string()).collect() }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum TextGranularity {
+    Words,
+    Lines,
+}
+
+impl TextGranularity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "words" => Some(TextGranularity::Words),
+            "lines" => Some(TextGranularity::Lines),
+            _ => None,
+        }
+    }
+}
+
+// Diff à l'intérieur des chaînes modifiées d'au moins `min_length` caractères
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct TextDiff {
+    pub granularity: TextGranularity,
+    pub min_length: usize,
+}
+
+impl TextDiff {
+    pub const DEFAULT_MIN_LENGTH: usize = 80;
+
+    pub fn new(granularity: TextGranularity) -> Self {
+        TextDiff { granularity, min_length: TextDiff::DEFAULT_MIN_LENGTH }
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Option<Self>, JsonDiffError> {
+        let fields = match value {
+            JsonValue::Null => return Ok(None),
+            JsonValue::Object(fields) => fields,
+            _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+        };
+        let mut text_diff = TextDiff::new(TextGranularity::Words);
+        for (key, val) in fields {
+            match (*key, val) {
+                ("granularity", JsonValue::String(name)) => {
+                    text_diff.granularity = TextGranularity::from_name(name).ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidOptions))?;
+                }
+                ("minLength", JsonValue::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => text_diff.min_length = *n as usize,
+                _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            }
+        }
+        Ok(Some(text_diff))
+    }
+}
+
+// Écart toléré entre deux nombres, absolu ou relatif à la plus grande des deux valeurs
+#[derive(Clone, Copy, PartialEq, Default)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Tolerance {
+    pub fn accepts(&self, a: f64, b: f64) -> bool {
+        let delta = (a - b).abs();
+        delta <= self.absolute || delta <= self.relative * a.abs().max(b.abs())
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, JsonDiffError> {
+        let JsonValue::Object(fields) = value else {
+            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+        };
+        let mut tolerance = Tolerance::default();
+        for (key, val) in fields {
+            match (*key, val) {
+                ("absolute", JsonValue::Number(n)) if *n >= 0.0 => tolerance.absolute = *n,
+                ("relative", JsonValue::Number(n)) if *n >= 0.0 => tolerance.relative = *n,
+                _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            }
+        }
+        Ok(tolerance)
+    }
+}
+
+// Normalisation des valeurs avant le diff, pour ne pas signaler les différences cosmétiques
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Normalization {
+    pub trim: bool,
+    // Composition Unicode NFC
+    pub unicode: bool,
+    // Blancs consécutifs réduits à une espace (implique `trim`)
+    pub collapse_whitespace: bool,
+    // `""` et `null` équivalent à un champ absent
+    pub empty_as_missing: bool,
+    pub case_fold: Vec<PathPattern>,
+}
+
+impl Normalization {
+    pub fn is_active(&self) -> bool {
+        self.trim || self.unicode || self.collapse_whitespace || self.empty_as_missing || !self.case_fold.is_empty()
+    }
+
+    pub fn folds_case(&self, path: &JsonPath) -> bool {
+        self.case_fold.iter().any(|p| p.matches(path))
+    }
+
+    // Active une étape par son nom, pour la ligne de commande
+    pub fn enable(&mut self, step: &str) -> bool {
+        match step {
+            "trim" => self.trim = true,
+            "unicode" => self.unicode = true,
+            "whitespace" => self.collapse_whitespace = true,
+            "empty" => self.empty_as_missing = true,
+            "all" => {
+                self.trim = true;
+                self.unicode = true;
+                self.collapse_whitespace = true;
+                self.empty_as_missing = true;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, JsonDiffError> {
+        let JsonValue::Object(fields) = value else {
+            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+        };
+        let mut n = Normalization::default();
+        for (key, val) in fields {
+            match (*key, val) {
+                ("trim", JsonValue::Boolean(b)) => n.trim = *b,
+                ("unicode", JsonValue::Boolean(b)) => n.unicode = *b,
+                ("collapseWhitespace", JsonValue::Boolean(b)) => n.collapse_whitespace = *b,
+                ("emptyAsMissing", JsonValue::Boolean(b)) => n.empty_as_missing = *b,
+                ("caseFold", list) => n.case_fold = pattern_list(&string_list(list)?)?,
+                _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            }
+        }
+        Ok(n)
+    }
+}
+
+// Appariement approché des entités supprimées et ajoutées, quand un import régénère les identifiants
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct FuzzyMatch {
+    // Similarité minimale, de 0 à 1
+    pub threshold: f64,
+    // Poids des champs, 1 par défaut
+    pub weights: Vec<(String, f64)>,
+}
+
+impl FuzzyMatch {
+    pub const DEFAULT_THRESHOLD: f64 = 0.8;
+
+    pub fn new(threshold: f64) -> Self {
+        FuzzyMatch { threshold, weights: Vec::new() }
+    }
+
+    pub fn weight(&self, field: &str) -> f64 {
+        self.weights.iter().find(|(f, _)| f == field).map_or(1.0, |(_, w)| *w)
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Option<Self>, JsonDiffError> {
+        let fields = match value {
+            JsonValue::Null => return Ok(None),
+            JsonValue::Object(fields) => fields,
+            _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+        };
+        let mut fuzzy = FuzzyMatch::new(FuzzyMatch::DEFAULT_THRESHOLD);
+        for (key, val) in fields {
+            match (*key, val) {
+                ("threshold", JsonValue::Number(n)) if (0.0..=1.0).contains(n) => fuzzy.threshold = *n,
+                ("weights", JsonValue::Object(weights)) => {
+                    fuzzy.weights = weights
+                        .iter()
+                        .map(|(field, w)| match w {
+                            JsonValue::Number(n) if *n >= 0.0 => Ok((field.to_string(), *n)),
+                            _ => Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+                        })
+                        .collect::<Result<_, JsonDiffError>>()?;
+                }
+                _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            }
+        }
+        Ok(Some(fuzzy))
+    }
+}
+
+#[derive(Clone)]
+pub struct DiffOptions {
+    pub collections: Vec<String>,
+    pub properties: Vec<String>,
+    pub missing_collections: MissingCollectionPolicy,
+    pub hierarchies: Vec<String>,
+    pub identity_key: Option<String>,
+    pub languages: Vec<Language>,
+    pub language: Option<String>,
+    pub text_diff: Option<TextDiff>,
+    pub sequences: Vec<PathPattern>,
+    pub sets: Vec<PathPattern>,
+    pub ignore: Vec<PathPattern>,
+    pub only: Vec<PathPattern>,
+    pub ignore_order: bool,
+    // Paire une clé supprimée et une clé ajoutée de même valeur comme un renommage
+    pub detect_renames: bool,
+    // Apparie des entités supprimées et ajoutées semblables ; exige une clé d'identité
+    pub fuzzy_match: Option<FuzzyMatch>,
+    pub tolerance: Option<Tolerance>,
+    // Tolérances propres à certains chemins, prioritaires sur la tolérance globale
+    pub tolerances: Vec<(PathPattern, Tolerance)>,
+    pub normalization: Normalization,
+    // Collections où un changement de type fait échouer le diff
+    pub strict_types: Vec<String>,
+}
+
+impl DiffOptions {
+    // Collections et propriétés racine d'un jeu de données Hazo
+    pub fn hazo() -> Self {
+        DiffOptions {
+            collections: ["taxons", "characters", "states", "books"].iter().map(|s| s.to_string()).collect(),
+            properties: vec!["id".to_string()],
+            missing_collections: MissingCollectionPolicy::Strict,
+            hierarchies: vec!["taxons".to_string()],
+            identity_key: None,
+            languages: vec![
+                Language::new("default", &["name"]),
+                Language::new("vernacular", &["vernacularName"]),
+                Language::new("en", &["nameEN"]),
+                Language::new("cn", &["nameCN"]),
+            ],
+            language: None,
+            text_diff: None,
+            sequences: Vec::new(),
+            sets: Vec::new(),
+            ignore: Vec::new(),
+            only: Vec::new(),
+            ignore_order: false,
+            detect_renames: false,
+            fuzzy_match: None,
+            tolerance: None,
+            tolerances: Vec::new(),
+            normalization: Normalization::default(),
+            strict_types: Vec::new(),
+        }
+    }
+
+    // Préréglage Hazo dont les entités sont appariées par `id`, comme pour les résumés et rendus
+    pub fn hazo_entities() -> Self {
+        DiffOptions { identity_key: Some("id".to_string()), ..DiffOptions::hazo() }
+    }
+
+    pub fn from_json(value: &JsonValue) -> Result<Self, JsonDiffError> {
+        let mut opts = DiffOptions::hazo();
+        let fields = match value {
+            JsonValue::Object(fields) => fields,
+            _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+        };
+        for (key, val) in fields {
+            match *key {
+                "collections" => opts.collections = string_list(val)?,
+                "properties" => opts.properties = string_list(val)?,
+                "hierarchies" => opts.hierarchies = string_list(val)?,
+                "identityKey" => {
+                    opts.identity_key = match val {
+                        JsonValue::String(key) => Some(key.to_string()),
+                        JsonValue::Null => None,
+                        _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+                    };
+                }
+                "languages" => {
+                    let JsonValue::Object(langs) = val else {
+                        return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+                    };
+                    opts.languages = langs
+                        .iter()
+                        .map(|(code, fields)| Ok(Language { code: code.to_string(), fields: string_list(fields)? }))
+                        .collect::<Result<_, JsonDiffError>>()?;
+                }
+                "language" => {
+                    opts.language = match val {
+                        JsonValue::String(code) => Some(code.to_string()),
+                        JsonValue::Null => None,
+                        _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+                    };
+                }
+                "sequences" => opts.sequences = pattern_list(&string_list(val)?)?,
+                "sets" => opts.sets = pattern_list(&string_list(val)?)?,
+                "ignore" => opts.ignore = pattern_list(&string_list(val)?)?,
+                "only" => opts.only = pattern_list(&string_list(val)?)?,
+                "ignoreOrder" => {
+                    opts.ignore_order = match val {
+                        JsonValue::Boolean(b) => *b,
+                        _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+                    };
+                }
+                "detectRenames" => {
+                    opts.detect_renames = match val {
+                        JsonValue::Boolean(b) => *b,
+                        _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+                    };
+                }
+                "fuzzyMatch" => opts.fuzzy_match = FuzzyMatch::from_json(val)?,
+                "tolerance" => {
+                    opts.tolerance = match val {
+                        JsonValue::Null => None,
+                        other => Some(Tolerance::from_json(other)?),
+                    };
+                }
+                "tolerances" => {
+                    let JsonValue::Object(paths) = val else {
+                        return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+                    };
+                    opts.tolerances = paths
+                        .iter()
+                        .map(|(pointer, t)| Ok((PathPattern::parse(pointer)?, Tolerance::from_json(t)?)))
+                        .collect::<Result<_, JsonDiffError>>()?;
+                }
+                "strictTypes" => opts.strict_types = string_list(val)?,
+                "normalize" => opts.normalization = Normalization::from_json(val)?,
+                "textDiff" => opts.text_diff = TextDiff::from_json(val)?,
+                "missingCollections" => {
+                    opts.missing_collections = match val {
+                        JsonValue::String(name) => MissingCollectionPolicy::from_name(name),
+                        _ => None,
+                    }
+                    .ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidOptions))?;
+                }
+                _ => return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            }
+        }
+        if let Some(code) = &opts.language
+            && !opts.languages.iter().any(|l| &l.code == code) {
+            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+        }
+        // L'appariement approché ne s'applique qu'aux tableaux appariés par clé d'identité
+        if opts.fuzzy_match.is_some() && opts.identity_key.is_none() {
+            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+        }
+        Ok(opts)
+    }
+
+    pub fn is_collection(&self, key: &str) -> bool {
+        self.collections.iter().any(|c| c == key)
+    }
+
+    pub fn is_strict_types(&self, key: &str) -> bool {
+        self.strict_types.iter().any(|c| c == key)
+    }
+
+    pub fn is_hierarchy(&self, key: &str) -> bool {
+        self.hierarchies.iter().any(|h| h == key)
+    }
+
+    pub fn language_of(&self, field: &str) -> Option<&str> {
+        self.languages.iter().find(|l| l.fields.iter().any(|f| f == field)).map(|l| l.code.as_str())
+    }
+
+    // Avec un filtre de langue, seuls les champs de cette langue sont comparés dans les entités
+    pub fn keeps_entity_field(&self, field: &str) -> bool {
+        match &self.language {
+            Some(code) => self.language_of(field) == Some(code.as_str()),
+            None => true,
+        }
+    }
+
+    // Tableaux sans clé d'identité comparés comme des séquences plutôt qu'indice par indice
+    pub fn is_sequence(&self, path: &JsonPath) -> bool {
+        self.sequences.iter().any(|p| p.matches(path))
+    }
+
+    // Tableaux comparés comme des ensembles, sans tenir compte de l'ordre ni des positions
+    pub fn is_set(&self, path: &JsonPath) -> bool {
+        self.sets.iter().any(|p| p.matches(path))
+    }
+
+    pub fn tolerance_at(&self, path: &JsonPath) -> Option<Tolerance> {
+        self.tolerances.iter().find(|(p, _)| p.matches(path)).map(|(_, t)| *t).or(self.tolerance)
+    }
+
+    pub fn filters_paths(&self) -> bool {
+        !self.ignore.is_empty() || !self.only.is_empty()
+    }
+
+    // Chemins écartés du diff : ignorés explicitement, ou hors des sous-arbres retenus par `only`
+    // (les ancêtres d'un sous-arbre retenu restent parcourus)
+    pub fn is_excluded(&self, path: &JsonPath) -> bool {
+        self.ignore.iter().any(|p| p.covers(path))
+            || (!self.only.is_empty() && !self.only.iter().any(|p| p.covers(path) || p.may_match_below(path)))
+    }
+
+    pub fn is_known_property(&self, key: &str) -> bool {
+        self.properties.iter().any(|p| p == key)
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions::hazo()
+    }
+}
+
+pub(crate) fn string_list(value: &JsonValue) -> Result<Vec<String>, JsonDiffError> {
+    match value {
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                JsonValue::String(s) => Ok(s.to_string()),
+                _ => Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+            })
+            .collect(),
+        _ => Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn test_hazo_preset() {
+        let opts = DiffOptions::hazo();
+        assert_eq!(opts.collections, vec!["taxons", "characters", "states", "books"]);
+        assert_eq!(opts.identity_key, None);
+        assert!(opts.is_known_property("id"));
+        assert!(!opts.is_collection("id"));
+    }
+
+    #[test]
+    fn test_from_json() {
+        let v = parse_json(r#"{"collections":["taxons","descriptors"]}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        assert_eq!(opts.collections, vec!["taxons", "descriptors"]);
+        assert_eq!(opts.properties, vec!["id"]);
+        assert_eq!(opts.missing_collections, MissingCollectionPolicy::Strict);
+        let v = parse_json(r#"{"missingCollections":"empty"}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        assert_eq!(opts.missing_collections, MissingCollectionPolicy::TreatAsEmpty);
+    }
+
+    #[test]
+    fn test_from_json_invalid() {
+        let v = parse_json(r#"{"collections":"taxons"}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"missingCollections":"lenient"}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"language":"fr"}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"unknown":[]}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+
+    #[test]
+    fn test_languages() {
+        let v = parse_json(r#"{"languages":{"fr":["name","vernacularName"],"en":["nameEN"]},"language":"fr"}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        assert_eq!(opts.languages, vec![Language::new("fr", &["name", "vernacularName"]), Language::new("en", &["nameEN"])]);
+        assert_eq!(opts.language_of("vernacularName"), Some("fr"));
+        assert_eq!(opts.language_of("detail"), None);
+        assert!(opts.keeps_entity_field("name"));
+        assert!(!opts.keeps_entity_field("nameEN"));
+        assert!(!opts.keeps_entity_field("detail"));
+    }
+
+    #[test]
+    fn test_text_diff() {
+        let v = parse_json(r#"{"textDiff":{"granularity":"lines","minLength":20}}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        assert_eq!(opts.text_diff, Some(TextDiff { granularity: TextGranularity::Lines, min_length: 20 }));
+        let v = parse_json(r#"{"textDiff":{}}"#).unwrap();
+        assert_eq!(DiffOptions::from_json(&v).unwrap().text_diff, Some(TextDiff::new(TextGranularity::Words)));
+        let v = parse_json(r#"{"textDiff":{"granularity":"chars"}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"textDiff":{"minLength":-1}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+
+    #[test]
+    fn test_sequences() {
+        let v = parse_json(r#"{"sequences":["/taxons/*/children"]}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        let mut path = JsonPath::from_key("taxons");
+        path.push_index(0);
+        assert!(!opts.is_sequence(&path));
+        path.push("children");
+        assert!(opts.is_sequence(&path));
+        let v = parse_json(r#"{"sequences":["children"]}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"sets":["/books/*/authors"]}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        let mut path = JsonPath::from_key("books");
+        path.push_index(3);
+        path.push("authors");
+        assert!(opts.is_set(&path));
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let v = parse_json(r#"{"tolerance":{"absolute":1e-9},"tolerances":{"/taxons/*/height":{"relative":0.01}}}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        let global = opts.tolerance_at(&JsonPath::from_key("books")).unwrap();
+        assert!(global.accepts(0.30000000000000004, 0.3));
+        assert!(!global.accepts(1.0, 1.001));
+        let mut path = JsonPath::from_key("taxons");
+        path.push_index(0);
+        path.push("height");
+        let height = opts.tolerance_at(&path).unwrap();
+        assert!(height.accepts(100.0, 100.9));
+        assert!(!height.accepts(100.0, 102.0));
+        let v = parse_json(r#"{"tolerance":{"absolute":-1}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        let v = parse_json(r#"{"identityKey":"id","fuzzyMatch":{"threshold":0.7,"weights":{"name":3}}}"#).unwrap();
+        let fuzzy = DiffOptions::from_json(&v).unwrap().fuzzy_match.unwrap();
+        assert_eq!(fuzzy.threshold, 0.7);
+        assert_eq!(fuzzy.weight("name"), 3.0);
+        assert_eq!(fuzzy.weight("detail"), 1.0);
+        let v = parse_json(r#"{"fuzzyMatch":{"threshold":2}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+        let v = parse_json(r#"{"identityKey":null,"fuzzyMatch":{"threshold":0.7}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+
+    #[test]
+    fn test_normalization() {
+        let v = parse_json(r#"{"normalize":{"trim":true,"emptyAsMissing":true,"caseFold":["/taxons/*/name"]}}"#).unwrap();
+        let n = DiffOptions::from_json(&v).unwrap().normalization;
+        assert!(n.is_active() && n.trim && n.empty_as_missing && !n.unicode);
+        let mut path = JsonPath::from_key("taxons");
+        path.push_index(0);
+        path.push("name");
+        assert!(n.folds_case(&path));
+        assert!(!DiffOptions::hazo().normalization.is_active());
+        let v = parse_json(r#"{"normalize":{"nfc":true}}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+
+    #[test]
+    fn test_path_filters() {
+        let v = parse_json(r#"{"ignore":["/**/modifiedAt"],"only":["/taxons/*/detail"]}"#).unwrap();
+        let opts = DiffOptions::from_json(&v).unwrap();
+        let mut path = JsonPath::from_key("taxons");
+        assert!(!opts.is_excluded(&path));
+        path.push_index(2);
+        path.push("name");
+        assert!(opts.is_excluded(&path));
+        path.pop();
+        path.push("detail");
+        assert!(!opts.is_excluded(&path));
+        path.pop();
+        path.push("modifiedAt");
+        assert!(opts.is_excluded(&path));
+        assert!(opts.is_excluded(&JsonPath::from_key("books")));
+    }
+
+    #[test]
+    fn test_ignore_order() {
+        let v = parse_json(r#"{"ignoreOrder":true}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).unwrap().ignore_order);
+        let v = parse_json(r#"{"ignoreOrder":"yes"}"#).unwrap();
+        assert!(DiffOptions::from_json(&v).is_err());
+    }
+}
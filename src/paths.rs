@@ -0,0 +1,220 @@
+use crate::errors::{JsonDiffError, JsonDiffErrorType};
+
+// Chemin courant pendant le parcours du diff, un segment par clé ou indice
+#[derive(Default)]
+pub struct JsonPath {
+    segments: Vec<String>,
+    // Identifiant des entités appariées par clé, affiché à la place de leur indice
+    ids: Vec<Option<String>>,
+    // Emplacement du premier changement de type rencontré
+    type_change: Option<String>,
+    // Le parcours s'arrête au premier changement, le diff écrit étant alors incomplet
+    first_change_only: bool,
+}
+
+impl JsonPath {
+    pub fn root() -> Self {
+        JsonPath::default()
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        let mut path = JsonPath::root();
+        path.push(key);
+        path
+    }
+
+    pub fn push(&mut self, segment: &str) {
+        self.segments.push(segment.to_string());
+        self.ids.push(None);
+    }
+
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(index.to_string());
+        self.ids.push(None);
+    }
+
+    // Élément apparié par clé : les motifs voient son indice, les messages son identifiant
+    pub fn push_entity(&mut self, index: usize, id: &str) {
+        self.segments.push(index.to_string());
+        self.ids.push(Some(id.to_string()));
+    }
+
+    pub fn pop(&mut self) {
+        self.segments.pop();
+        self.ids.pop();
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    // Chemin lisible où les entités sont désignées par leur identifiant
+    pub fn location(&self) -> String {
+        let mut location = String::new();
+        for (segment, id) in self.segments.iter().zip(&self.ids) {
+            location.push('/');
+            location.push_str(id.as_deref().unwrap_or(segment));
+        }
+        location
+    }
+
+    pub fn record_type_change(&mut self) {
+        if self.type_change.is_none() {
+            self.type_change = Some(self.location());
+        }
+    }
+
+    pub fn take_type_change(&mut self) -> Option<String> {
+        self.type_change.take()
+    }
+
+    pub fn stop_at_first_change(&mut self) {
+        self.first_change_only = true;
+    }
+
+    pub fn stops_at_first_change(&self) -> bool {
+        self.first_change_only
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{}", segment.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+enum PatternSegment {
+    Key(String),
+    // `*` : un segment quelconque
+    Any,
+    // `**` : zéro ou plusieurs segments
+    AnyDepth,
+}
+
+// Motif JSON Pointer (RFC 6901) étendu aux jokers `*` et `**`, par exemple `/taxons/*/children`
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct PathPattern {
+    source: String,
+    segments: Vec<PatternSegment>,
+}
+
+impl PathPattern {
+    pub fn parse(pointer: &str) -> Result<Self, JsonDiffError> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(JsonDiffError::new(JsonDiffErrorType::InvalidOptions));
+        }
+        let segments = pointer.split('/').skip(1).map(|s| match s {
+            "*" => PatternSegment::Any,
+            "**" => PatternSegment::AnyDepth,
+            key => PatternSegment::Key(key.replace("~1", "/").replace("~0", "~")),
+        }).collect();
+        Ok(PathPattern { source: pointer.to_string(), segments })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn matches(&self, path: &JsonPath) -> bool {
+        matches_segments(&self.segments, path.segments())
+    }
+
+    // Vrai si le chemin ou l'un de ses ancêtres correspond au motif
+    pub fn covers(&self, path: &JsonPath) -> bool {
+        let segments = path.segments();
+        (0..=segments.len()).any(|n| matches_segments(&self.segments, &segments[..n]))
+    }
+
+    // Vrai si un descendant du chemin peut encore correspondre au motif
+    pub fn may_match_below(&self, path: &JsonPath) -> bool {
+        prefix_matches(&self.segments, path.segments())
+    }
+}
+
+fn matches_segments(pattern: &[PatternSegment], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((PatternSegment::AnyDepth, rest)) => (0..=path.len()).any(|i| matches_segments(rest, &path[i..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((head, tail)) => (*segment == PatternSegment::Any || *segment == PatternSegment::Key(head.clone())) && matches_segments(rest, tail),
+            None => false,
+        },
+    }
+}
+
+fn prefix_matches(pattern: &[PatternSegment], path: &[String]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some((PatternSegment::AnyDepth, _)), _) => true,
+        (Some((segment, rest)), Some((head, tail))) => {
+            (*segment == PatternSegment::Any || *segment == PatternSegment::Key(head.clone())) && prefix_matches(rest, tail)
+        }
+    }
+}
+
+pub(crate) fn pattern_list(values: &[String]) -> Result<Vec<PathPattern>, JsonDiffError> {
+    values.iter().map(|v| PathPattern::parse(v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(segments: &[&str]) -> JsonPath {
+        let mut p = JsonPath::root();
+        for s in segments {
+            p.push(s);
+        }
+        p
+    }
+
+    #[test]
+    fn test_path_pattern() {
+        let p = PathPattern::parse("/taxons/*/children").unwrap();
+        assert!(p.matches(&path(&["taxons", "3", "children"])));
+        assert!(!p.matches(&path(&["taxons", "3"])));
+        assert!(!p.matches(&path(&["characters", "3", "children"])));
+        assert!(p.may_match_below(&path(&["taxons", "3"])));
+        assert!(!p.may_match_below(&path(&["states"])));
+        assert!(p.covers(&path(&["taxons", "3", "children", "0"])));
+        assert!(!p.covers(&path(&["taxons", "3"])));
+
+        let p = PathPattern::parse("/**/a~1b").unwrap();
+        assert!(p.matches(&path(&["a/b"])));
+        assert!(p.matches(&path(&["x", "y", "a/b"])));
+        assert!(p.may_match_below(&path(&["x"])));
+
+        assert!(PathPattern::parse("").unwrap().matches(&JsonPath::root()));
+        assert!(PathPattern::parse("taxons").is_err());
+    }
+
+    #[test]
+    fn test_json_path_location() {
+        let mut p = JsonPath::from_key("taxons");
+        p.push_entity(3, "t1");
+        p.push("height");
+        assert!(PathPattern::parse("/taxons/3/height").unwrap().matches(&p));
+        assert_eq!(p.location(), "/taxons/t1/height");
+        p.record_type_change();
+        p.pop();
+        p.push("name");
+        p.record_type_change();
+        assert_eq!(p.take_type_change().as_deref(), Some("/taxons/t1/height"));
+        assert_eq!(p.take_type_change(), None);
+    }
+
+    #[test]
+    fn test_json_path_display() {
+        let mut p = JsonPath::from_key("taxons");
+        p.push_index(2);
+        p.push("a/b~");
+        assert_eq!(p.to_string(), "/taxons/2/a~1b~0");
+    }
+}
@@ -0,0 +1,361 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::json::{unescape, JsonValue};
+use crate::jsondiff::json_value_to_string;
+use crate::options::DiffOptions;
+use crate::summary::{collection_label, entity_label, index_move, is_scalar_change};
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum DiffFormat {
+    Json,
+    Text,
+    Html,
+    Markdown,
+}
+
+impl DiffFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(DiffFormat::Json),
+            "text" => Some(DiffFormat::Text),
+            "html" => Some(DiffFormat::Html),
+            "markdown" => Some(DiffFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+// Noms des entités des deux versions, pour afficher autre chose qu'un identifiant
+pub struct EntityNames<'a> {
+    names: HashMap<(&'a str, &'a str), &'a str>,
+}
+
+impl<'a> EntityNames<'a> {
+    pub fn new(old: &JsonValue<'a>, new: &JsonValue<'a>, opts: &'a DiffOptions) -> Self {
+        let mut names = HashMap::new();
+        // La nouvelle version l'emporte : elle est insérée en dernier
+        for root in [old, new] {
+            for collection in &opts.collections {
+                for entity in root.get(collection).and_then(|v| v.as_array()).unwrap_or(&[]) {
+                    let id = entity.get("id").and_then(|v| v.as_str());
+                    let name = entity.get("name").or_else(|| entity.get("label")).and_then(|v| v.as_str());
+                    if let (Some(id), Some(name)) = (id, name) {
+                        names.insert((collection.as_str(), id), name);
+                    }
+                }
+            }
+        }
+        EntityNames { names }
+    }
+
+    pub fn empty() -> Self {
+        EntityNames { names: HashMap::new() }
+    }
+
+    pub fn name(&self, collection: &str, id: &str) -> Option<Cow<'a, str>> {
+        self.names.get(&(collection, id)).map(|n| unescape(n))
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct TextWriter {
+    out: String,
+    color: bool,
+}
+
+impl TextWriter {
+    fn line(&mut self, indent: usize, style: &str, text: &str) {
+        for _ in 0..indent {
+            self.out.push_str("  ");
+        }
+        if self.color && !style.is_empty() {
+            self.out.push_str(style);
+            self.out.push_str(text);
+            self.out.push_str(RESET);
+        } else {
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+}
+
+pub(crate) fn display_value(v: &JsonValue) -> String {
+    match v {
+        JsonValue::String(s) => format!("\"{}\"", unescape(s)),
+        other => json_value_to_string(other),
+    }
+}
+
+fn is_collection_change(v: &JsonValue) -> bool {
+    matches!(v, JsonValue::Object(f) if !f.is_empty() && f.iter().all(|(k, _)| matches!(*k, "added" | "removed" | "modified" | "moved")))
+}
+
+pub(crate) fn entity_title(collection: &str, entity: &JsonValue, names: &EntityNames) -> String {
+    let id = entity.get("id").map_or(Cow::Borrowed("?"), |v| match v {
+        JsonValue::String(s) => unescape(s),
+        other => Cow::Owned(json_value_to_string(other)),
+    });
+    let own_name = entity.get("name").or_else(|| entity.get("label")).and_then(|v| v.as_str()).map(unescape);
+    match own_name.or_else(|| names.name(collection, &id)) {
+        Some(name) => format!("{} {} ({})", entity_label(collection), id, name),
+        None => format!("{} {}", entity_label(collection), id),
+    }
+}
+
+fn write_entries(w: &mut TextWriter, indent: usize, sign: &str, style: &str, v: Option<&JsonValue>) {
+    match v {
+        Some(JsonValue::Array(items)) => {
+            for item in items {
+                w.line(indent, style, &format!("{} {}", sign, display_value(item)));
+            }
+        }
+        Some(JsonValue::Object(fields)) => {
+            for (k, item) in fields {
+                w.line(indent, style, &format!("{} {}: {}", sign, k, display_value(item)));
+            }
+        }
+        _ => {}
+    }
+}
+
+// Morceaux d'un diff textuel : en couleur comme `git diff --word-diff=color`, sinon avec
+// les marqueurs [-supprimé-] et {+inséré+}
+fn write_hunks(w: &mut TextWriter, indent: usize, hunks: &[JsonValue]) {
+    let mut text = String::new();
+    for hunk in hunks {
+        let Some(part) = hunk.get("text").and_then(|t| t.as_str()).map(unescape) else { continue };
+        let (open, close) = match (hunk.get("op").and_then(|o| o.as_str()), w.color) {
+            (Some("delete"), true) => (RED, RESET),
+            (Some("insert"), true) => (GREEN, RESET),
+            (Some("delete"), false) => ("[-", "-]"),
+            (Some("insert"), false) => ("{+", "+}"),
+            _ => ("", ""),
+        };
+        // Les styles ne débordent pas d'une ligne à l'autre
+        for (i, line) in part.split('\n').enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            if !line.is_empty() {
+                text.push_str(open);
+                text.push_str(line);
+                text.push_str(close);
+            }
+        }
+    }
+    for line in text.trim_end_matches('\n').split('\n') {
+        w.line(indent, "", &format!("~ {}", line));
+    }
+}
+
+// Diff de séquence : positions dans le nouveau tableau pour les insertions, dans l'ancien pour les suppressions
+fn write_positions(w: &mut TextWriter, indent: usize, sign: &str, style: &str, v: Option<&JsonValue>) {
+    for entry in v.and_then(|v| v.as_array()).unwrap_or(&[]) {
+        let index = entry.get("index").map_or(String::new(), json_value_to_string);
+        let value = entry.get("value").map_or(String::new(), display_value);
+        w.line(indent, style, &format!("{} [{}] {}", sign, index, value));
+    }
+}
+
+fn write_change(w: &mut TextWriter, indent: usize, change: &JsonValue) {
+    if let Some(JsonValue::Array(hunks)) = change.get("hunks") {
+        write_hunks(w, indent, hunks);
+    } else if change.get("inserted").is_some() && change.get("deleted").is_some() {
+        write_positions(w, indent, "-", RED, change.get("deleted"));
+        write_positions(w, indent, "+", GREEN, change.get("inserted"));
+    } else if is_scalar_change(change) {
+        w.line(indent, RED, &format!("- {}", display_value(change.get("old").unwrap())));
+        w.line(indent, GREEN, &format!("+ {}", display_value(change.get("new").unwrap())));
+        if let (Some(old_type), Some(new_type)) = (change.get("oldType"), change.get("newType")) {
+            w.line(indent, YELLOW, &format!("! type changed from {} to {}", display_value(old_type), display_value(new_type)));
+        }
+    } else if is_collection_change(change) {
+        write_entries(w, indent, "+", GREEN, change.get("added"));
+        write_entries(w, indent, "-", RED, change.get("removed"));
+        for m in change.get("moved").and_then(|v| v.as_array()).unwrap_or(&[]) {
+            let item = m.get("value").or_else(|| m.get("id")).map_or("?".to_string(), display_value);
+            w.line(indent, YELLOW, &format!("> {} {}", item, move_text(m)));
+        }
+        match change.get("modified") {
+            Some(JsonValue::Array(entries)) => {
+                for entry in entries {
+                    if is_scalar_change(entry) {
+                        write_change(w, indent, entry);
+                        continue;
+                    }
+                    let identity = entry_identity(entry);
+                    match identity.and_then(|k| entry.get(k).map(|v| (k, v))) {
+                        Some(("index", i)) => w.line(indent, YELLOW, &format!("~ [{}]", display_value(i))),
+                        Some((_, id)) => w.line(indent, YELLOW, &format!("~ {}", display_value(id))),
+                        None => w.line(indent, YELLOW, "~"),
+                    }
+                    write_fields(w, indent + 1, entry, identity);
+                }
+            }
+            Some(modified) => write_fields(w, indent, modified, None),
+            None => {}
+        }
+    } else {
+        write_fields(w, indent, change, None);
+    }
+}
+
+// Champ désignant l'élément modifié : `id` pour une entité, `index` pour un élément comparé par position
+fn entry_identity(entry: &JsonValue) -> Option<&'static str> {
+    ["id", "index"].into_iter().find(|k| entry.get(k).is_some())
+}
+
+// Diff à plat d'un objet : un champ par clé, `added` et `removed` regroupant les champs apparus ou disparus.
+// Le champ `identity` qui désigne l'élément n'est pas une modification.
+fn write_fields(w: &mut TextWriter, indent: usize, diff: &JsonValue, identity: Option<&str>) {
+    let JsonValue::Object(fields) = diff else { return };
+    let entity = identity.is_some();
+    for (field, change) in fields {
+        match *field {
+            f if identity == Some(f) => {}
+            "added" if matches!(change, JsonValue::Object(_)) => write_entries(w, indent, "+", GREEN, Some(change)),
+            "removed" if matches!(change, JsonValue::Object(_)) => write_entries(w, indent, "-", RED, Some(change)),
+            "replaces" if entity => w.line(indent, YELLOW, &format!("id changed from {}", display_value(change))),
+            "renamed" if let JsonValue::Object(renames) = change => {
+                for (old, new) in renames {
+                    w.line(indent, YELLOW, &format!("> {} renamed to {}", old, display_value(new)));
+                }
+            }
+            _ => {
+                w.line(indent, BOLD, field);
+                write_change(w, indent + 1, change);
+            }
+        }
+    }
+}
+
+// « from X to Y » pour un déplacement dans la hiérarchie ou dans l'ordre d'un tableau
+pub(crate) fn move_text(m: &JsonValue) -> String {
+    if let Some((from, to)) = index_move(m) {
+        return format!("from position {} to {}", from, to);
+    }
+    let parent = |key| m.get(key).and_then(|v| v.as_str()).map_or(Cow::Borrowed("the root"), unescape);
+    format!("from {} to {}", parent("from"), parent("to"))
+}
+
+// Rendu texte sans couleur d'une modification imbriquée
+pub(crate) fn change_to_text(change: &JsonValue) -> String {
+    let mut w = TextWriter { out: String::new(), color: false };
+    write_change(&mut w, 0, change);
+    w.out
+}
+
+pub fn render_text(diff: &JsonValue, names: &EntityNames, color: bool) -> String {
+    let mut w = TextWriter { out: String::new(), color };
+    let JsonValue::Object(collections) = diff else { return w.out };
+    for (collection, value) in collections {
+        if let JsonValue::Object(parts) = value
+            && parts.iter().all(|(_, v)| matches!(v, JsonValue::Array(items) if items.is_empty()))
+        {
+            continue;
+        }
+        if !w.out.is_empty() {
+            w.out.push('\n');
+        }
+        match *collection {
+            "unknown" => {
+                w.line(0, BOLD, "Unknown root keys");
+                write_entries(&mut w, 1, "?", YELLOW, Some(value));
+            }
+            "properties" => {
+                w.line(0, BOLD, collection_label(collection));
+                write_change(&mut w, 1, value);
+            }
+            _ => {
+                w.line(0, BOLD, collection_label(collection));
+                let items = |name: &str| value.get(name).and_then(|v| v.as_array()).unwrap_or(&[]);
+                for entity in items("added") {
+                    w.line(1, GREEN, &format!("+ {}", entity_title(collection, entity, names)));
+                }
+                for entity in items("removed") {
+                    w.line(1, RED, &format!("- {}", entity_title(collection, entity, names)));
+                }
+                for m in items("moved") {
+                    w.line(1, YELLOW, &format!("> {} moved {}", entity_title(collection, m, names), move_text(m)));
+                }
+                for entry in items("modified") {
+                    w.line(1, YELLOW, &format!("~ {}", entity_title(collection, entry, names)));
+                    write_fields(&mut w, 2, entry, entry_identity(entry));
+                }
+            }
+        }
+    }
+    w.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DIFF: &str = r#"{
+        "taxons":{"added":[{"id":"t9","name":"Ficus"}],"removed":[],
+                  "modified":[{"id":"t1","name":{"old":"Acanthaceae","new":"Acanthaceae s.l."},"children":{"added":["t5"],"removed":[]},"added":{"nameEN":"Acanthus family"}}],
+                  "moved":[{"id":"t4","from":"t1","to":null,"descendants":[]}]},
+        "properties":{"modified":{"id":{"old":"ds1","new":"ds2"}}}
+    }"#;
+
+    #[test]
+    fn test_render_text() {
+        let diff = parse_json(DIFF).unwrap();
+        let old = parse_json(r#"{"taxons":[{"id":"t4","name":"Ruellia"}]}"#).unwrap();
+        let opts = DiffOptions::hazo();
+        let names = EntityNames::new(&old, &old, &opts);
+        assert_eq!(render_text(&diff, &names, false), concat!(
+            "Taxa\n",
+            "  + Taxon t9 (Ficus)\n",
+            "  > Taxon t4 (Ruellia) moved from t1 to the root\n",
+            "  ~ Taxon t1\n",
+            "    name\n",
+            "      - \"Acanthaceae\"\n",
+            "      + \"Acanthaceae s.l.\"\n",
+            "    children\n",
+            "      + \"t5\"\n",
+            "    + nameEN: \"Acanthus family\"\n",
+            "\n",
+            "Dataset\n",
+            "  id\n",
+            "    - \"ds1\"\n",
+            "    + \"ds2\"\n",
+        ));
+    }
+
+    #[test]
+    fn test_render_text_sequence() {
+        let diff = parse_json(r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","path":{"inserted":[{"index":0,"value":"t0"}],"deleted":[{"index":1,"value":"t2"}]}}]}}"#).unwrap();
+        assert_eq!(render_text(&diff, &EntityNames::empty(), false), "Taxa\n  ~ Taxon t1\n    path\n      - [1] \"t2\"\n      + [0] \"t0\"\n");
+    }
+
+    #[test]
+    fn test_render_text_positional_entry() {
+        let diff = parse_json(r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","bookInfoByIds":{"added":[],"removed":[],"modified":[{"index":2,"fasc":{"old":1,"new":2}}]}}]}}"#).unwrap();
+        assert_eq!(render_text(&diff, &EntityNames::empty(), false), "Taxa\n  ~ Taxon t1\n    bookInfoByIds\n      ~ [2]\n        fasc\n          - 1\n          + 2\n");
+    }
+
+    #[test]
+    fn test_render_text_color() {
+        let diff = parse_json(r#"{"books":{"added":[],"removed":[{"id":"b1","label":"Flora"}]}}"#).unwrap();
+        let text = render_text(&diff, &EntityNames::empty(), true);
+        assert_eq!(text, "\x1b[1mBooks\x1b[0m\n  \x1b[31m- Book b1 (Flora)\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_render_text_hunks() {
+        let diff = parse_json(r#"{"books":{"added":[],"removed":[],"modified":[{"id":"b1","label":{"old":"Flora of\nChina","new":"Flora of\nsouthern China","hunks":[
+            {"op":"equal","text":"Flora of\n"},{"op":"insert","text":"southern "},{"op":"equal","text":"China"}]}}]}}"#).unwrap();
+        assert_eq!(render_text(&diff, &EntityNames::empty(), false), "Books\n  ~ Book b1\n    label\n      ~ Flora of\n      ~ {+southern +}China\n");
+        assert!(render_text(&diff, &EntityNames::empty(), true).contains("~ \x1b[32msouthern \x1b[0mChina"));
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::errors::{JsonDiffError, JsonDiffErrorType};
+use crate::hazo::{Character, Dataset, Taxon};
+use crate::json::{escape, unescape, JsonValue};
+use crate::xml::{parse_xml, XmlElement, XmlWriter};
+
+const SDD_NAMESPACE: &str = "http://rs.tdwg.org/UBIF/2006/";
+const SDD_SCHEMA_LOCATION: &str = "http://rs.tdwg.org/UBIF/2006/ http://rs.tdwg.org/UBIF/2006/Schema/1.1/SDD.xsd";
+
+#[derive(Default)]
+#[cfg_attr(test, derive(Debug))]
+pub struct EntitySelection {
+    pub taxons: HashSet<String>,
+    pub characters: HashSet<String>,
+    pub states: HashSet<String>,
+    pub books: HashSet<String>,
+}
+
+impl EntitySelection {
+    // Entités ajoutées, modifiées ou déplacées d'après un diff calculé avec une clé d'identité
+    pub fn from_diff(diff: &JsonValue) -> Self {
+        let mut selection = EntitySelection::default();
+        for (collection, ids) in [
+            ("taxons", &mut selection.taxons),
+            ("characters", &mut selection.characters),
+            ("states", &mut selection.states),
+            ("books", &mut selection.books),
+        ] {
+            let Some(value) = diff.get(collection) else { continue };
+            let items = |name: &str| value.get(name).and_then(|v| v.as_array()).unwrap_or(&[]);
+            for entity in items("added").iter().chain(items("modified")) {
+                if let Some(id) = entity.get("id").and_then(|v| v.as_str()) {
+                    ids.insert(id.to_string());
+                }
+            }
+            for m in items("moved") {
+                let descendants = m.get("descendants").and_then(|v| v.as_array()).unwrap_or(&[]);
+                for id in std::iter::once(m.get("id")).flatten().chain(descendants).filter_map(|v| v.as_str()) {
+                    ids.insert(id.to_string());
+                }
+            }
+        }
+        selection
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.taxons.is_empty() && self.characters.is_empty() && self.states.is_empty() && self.books.is_empty()
+    }
+
+    // Complète la sélection pour que le document reste résolvable : ancêtres des taxons et
+    // des caractères, caractères décrits par les taxons et caractères portant les états.
+    fn close_over(&mut self, ds: &Dataset) {
+        let taxon_ancestors: Vec<&str> = ds.taxons.iter().filter(|t| self.taxons.contains(t.id)).flat_map(|t| t.path.iter().copied()).collect();
+        self.taxons.extend(taxon_ancestors.into_iter().map(String::from));
+        for t in ds.taxons.iter().filter(|t| self.taxons.contains(t.id)) {
+            self.characters.extend(t.descriptions.iter().map(|d| d.descriptor_id.to_string()));
+        }
+        for c in &ds.characters {
+            if c.states.iter().any(|s| self.states.contains(*s)) {
+                self.characters.insert(c.id.to_string());
+            }
+        }
+        let character_ancestors: Vec<&str> = ds.characters.iter().filter(|c| self.characters.contains(c.id)).flat_map(|c| c.path.iter().copied()).collect();
+        self.characters.extend(character_ancestors.into_iter().map(String::from));
+    }
+}
+
+// Noms traduits, écrits en libellés `xml:lang` à côté du nom par défaut
+const LOCALIZED_NAMES: [(&str, &str); 2] = [("en", "nameEN"), ("zh", "nameCN")];
+
+fn representation(w: &mut XmlWriter, label: &str, names: [&str; 2], detail: &str) {
+    w.open("Representation", &[]);
+    w.text("Label", &[], &unescape(label));
+    for ((lang, _), name) in LOCALIZED_NAMES.iter().zip(names) {
+        if !name.is_empty() {
+            w.text("Label", &[("xml:lang", lang)], &unescape(name));
+        }
+    }
+    if !detail.is_empty() {
+        w.text("Detail", &[], &unescape(detail));
+    }
+    w.close("Representation");
+}
+
+fn write_taxa(w: &mut XmlWriter, taxa: &[&Taxon]) {
+    if taxa.is_empty() {
+        return;
+    }
+    w.open("TaxonNames", &[]);
+    for t in taxa {
+        w.open("TaxonName", &[("id", t.id)]);
+        representation(w, t.name, [t.name_en, t.name_cn], t.detail);
+        w.close("TaxonName");
+    }
+    w.close("TaxonNames");
+
+    let included: HashSet<&str> = taxa.iter().map(|t| t.id).collect();
+    w.open("TaxonHierarchies", &[]);
+    w.open("TaxonHierarchy", &[("id", "th1")]);
+    representation(w, "Taxonomy", ["", ""], "");
+    w.text("TaxonHierarchyType", &[], "UnspecifiedTaxonomy");
+    w.open("Nodes", &[]);
+    for t in taxa {
+        w.open("Node", &[("id", &format!("tn_{}", t.id))]);
+        if let Some(parent) = t.path.last().filter(|p| included.contains(*p)) {
+            w.empty("Parent", &[("ref", &format!("tn_{}", parent))]);
+        }
+        w.empty("TaxonName", &[("ref", t.id)]);
+        w.close("Node");
+    }
+    w.close("Nodes");
+    w.close("TaxonHierarchy");
+    w.close("TaxonHierarchies");
+}
+
+fn write_characters(w: &mut XmlWriter, ds: &Dataset, characters: &[&Character]) {
+    if characters.is_empty() {
+        return;
+    }
+    w.open("Characters", &[]);
+    for c in characters {
+        w.open("CategoricalCharacter", &[("id", c.id)]);
+        representation(w, c.name, [c.name_en, c.name_cn], c.detail);
+        let states: Vec<_> = c.states.iter().filter_map(|s| ds.state(s)).collect();
+        if !states.is_empty() {
+            w.open("States", &[]);
+            for s in states {
+                w.open("StateDefinition", &[("id", s.id)]);
+                representation(w, s.name, [s.name_en, s.name_cn], s.description);
+                w.close("StateDefinition");
+            }
+            w.close("States");
+        }
+        w.close("CategoricalCharacter");
+    }
+    w.close("Characters");
+
+    let included: HashSet<&str> = characters.iter().map(|c| c.id).collect();
+    w.open("CharacterTrees", &[]);
+    w.open("CharacterTree", &[("id", "ct1")]);
+    representation(w, "Characters", ["", ""], "");
+    w.open("Nodes", &[]);
+    for c in characters {
+        w.open("CharNode", &[("id", &format!("cn_{}", c.id))]);
+        if let Some(parent) = c.path.last().filter(|p| included.contains(*p)) {
+            w.empty("Parent", &[("ref", &format!("cn_{}", parent))]);
+        }
+        w.empty("Character", &[("ref", c.id)]);
+        w.close("CharNode");
+    }
+    w.close("Nodes");
+    w.close("CharacterTree");
+    w.close("CharacterTrees");
+}
+
+fn write_descriptions(w: &mut XmlWriter, taxa: &[&Taxon]) {
+    let described: Vec<_> = taxa.iter().filter(|t| !t.descriptions.is_empty()).collect();
+    if described.is_empty() {
+        return;
+    }
+    w.open("CodedDescriptions", &[]);
+    for t in described {
+        w.open("CodedDescription", &[("id", &format!("D_{}", t.id))]);
+        representation(w, t.name, ["", ""], "");
+        w.open("Scope", &[]);
+        w.empty("TaxonName", &[("ref", t.id)]);
+        w.close("Scope");
+        w.open("SummaryData", &[]);
+        for d in &t.descriptions {
+            if d.states_ids.is_empty() {
+                w.empty("Categorical", &[("ref", d.descriptor_id)]);
+                continue;
+            }
+            w.open("Categorical", &[("ref", d.descriptor_id)]);
+            for s in &d.states_ids {
+                w.empty("State", &[("ref", s)]);
+            }
+            w.close("Categorical");
+        }
+        w.close("SummaryData");
+        w.close("CodedDescription");
+    }
+    w.close("CodedDescriptions");
+}
+
+pub fn dataset_to_sdd(ds: &Dataset, selection: Option<&EntitySelection>, created: &str) -> String {
+    let mut closed = EntitySelection::default();
+    if let Some(selection) = selection {
+        closed.taxons.clone_from(&selection.taxons);
+        closed.characters.clone_from(&selection.characters);
+        closed.states.clone_from(&selection.states);
+        closed.books.clone_from(&selection.books);
+        closed.close_over(ds);
+    }
+    let keep = |set: &HashSet<String>, id: &str| selection.is_none() || set.contains(id);
+    let taxa: Vec<&Taxon> = ds.taxons.iter().filter(|t| keep(&closed.taxons, t.id)).collect();
+    let characters: Vec<&Character> = ds.characters.iter().filter(|c| keep(&closed.characters, c.id)).collect();
+
+    let mut w = XmlWriter::new();
+    w.open("Datasets", &[
+        ("xmlns", SDD_NAMESPACE),
+        ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+        ("xsi:schemaLocation", SDD_SCHEMA_LOCATION),
+    ]);
+    w.open("TechnicalMetadata", &[("created", created)]);
+    w.empty("Generator", &[("name", "hazojsondiff"), ("version", env!("CARGO_PKG_VERSION"))]);
+    w.close("TechnicalMetadata");
+    w.open("Dataset", &[("xml:lang", "en")]);
+    representation(&mut w, ds.id.unwrap_or("Hazo dataset"), ["", ""], "");
+    write_taxa(&mut w, &taxa);
+    write_characters(&mut w, ds, &characters);
+    write_descriptions(&mut w, &taxa);
+    let books: Vec<_> = ds.books.iter().filter(|b| keep(&closed.books, b.id)).collect();
+    if !books.is_empty() {
+        w.open("Publications", &[]);
+        for b in books {
+            w.open("Publication", &[("id", b.id)]);
+            representation(&mut w, b.label, ["", ""], "");
+            w.close("Publication");
+        }
+        w.close("Publications");
+    }
+    w.close("Dataset");
+    w.close("Datasets");
+    w.finish()
+}
+
+#[derive(Default)]
+struct ImportedEntity {
+    id: String,
+    name: String,
+    // (champ Hazo, nom traduit)
+    localized: Vec<(&'static str, String)>,
+    detail: String,
+    path: Vec<String>,
+    children: Vec<String>,
+    // Taxons : (caractère, états) ; caractères : états
+    descriptions: Vec<(String, Vec<String>)>,
+    states: Vec<String>,
+}
+
+fn imported(e: &XmlElement) -> Option<ImportedEntity> {
+    let repr = e.child("Representation");
+    let labels = || repr.into_iter().flat_map(|r| r.children_named("Label"));
+    let name = labels().find(|l| l.attr("xml:lang").is_none()).map_or("", |l| &l.text).to_string();
+    let localized = labels()
+        .filter_map(|l| {
+            let (_, field) = LOCALIZED_NAMES.iter().find(|(lang, _)| l.attr("xml:lang") == Some(*lang))?;
+            Some((*field, l.text.clone()))
+        })
+        .collect();
+    let detail = repr.and_then(|r| r.child_text("Detail")).unwrap_or("").to_string();
+    Some(ImportedEntity { id: e.attr("id")?.to_string(), name, localized, detail, ..Default::default() })
+}
+
+fn ref_of<'x>(e: &'x XmlElement, name: &str) -> Option<&'x str> {
+    e.child(name).and_then(|c| c.attr("ref"))
+}
+
+// Reconstruit path et children à partir des nœuds d'une hiérarchie SDD (Node ou CharNode)
+fn apply_hierarchy(entities: &mut [ImportedEntity], nodes: &[&XmlElement], entity_tag: &str) {
+    let node_entity: HashMap<&str, &str> = nodes.iter()
+        .filter_map(|n| Some((n.attr("id")?, ref_of(n, entity_tag)?)))
+        .collect();
+    let parent_of: HashMap<&str, &str> = nodes.iter()
+        .filter_map(|n| Some((ref_of(n, entity_tag)?, *node_entity.get(ref_of(n, "Parent")?)?)))
+        .collect();
+    for entity in entities.iter_mut() {
+        let mut path = Vec::new();
+        let mut current = entity.id.as_str();
+        while let Some(parent) = parent_of.get(current) {
+            if path.len() > parent_of.len() {
+                break;
+            }
+            path.push(parent.to_string());
+            current = parent;
+        }
+        path.reverse();
+        entity.path = path;
+    }
+    for n in nodes {
+        if let Some(child) = ref_of(n, entity_tag)
+            && let Some(parent) = parent_of.get(child)
+            && let Some(e) = entities.iter_mut().find(|e| e.id == *parent)
+        {
+            e.children.push(child.to_string());
+        }
+    }
+}
+
+fn nodes_of<'x>(dataset: &'x XmlElement, container: &'x str, hierarchy: &'x str, node: &'x str) -> Vec<&'x XmlElement> {
+    dataset.children_named(container)
+        .flat_map(|c| c.children_named(hierarchy))
+        .filter_map(|h| h.child("Nodes"))
+        .flat_map(|n| n.children_named(node))
+        .collect()
+}
+
+fn push_string_list(s: &mut String, items: &[String]) {
+    s.push('[');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        s.push_str(&escape(item));
+    }
+    s.push(']');
+}
+
+// Les champs absents du document ne sont pas recréés avec une valeur par défaut : seuls
+// l'identifiant et le nom sont toujours écrits
+fn push_string_list_field(s: &mut String, key: &str, items: &[String]) {
+    if !items.is_empty() {
+        s.push_str(&format!(",\"{}\":", key));
+        push_string_list(s, items);
+    }
+}
+
+fn push_entity_start(s: &mut String, e: &ImportedEntity, detail_key: &str) {
+    s.push_str(&format!("{{\"id\":{},\"name\":{}", escape(&e.id), escape(&e.name)));
+    for (field, name) in &e.localized {
+        s.push_str(&format!(",\"{}\":{}", field, escape(name)));
+    }
+    if !e.detail.is_empty() {
+        s.push_str(&format!(",\"{}\":{}", detail_key, escape(&e.detail)));
+    }
+}
+
+// Convertit le premier jeu de données d'un document SDD vers la structure JSON Hazo
+pub fn sdd_to_hazo_json(xml: &str) -> Result<String, JsonDiffError> {
+    let root = parse_xml(xml)?;
+    let dataset = if root.local_name() == "Datasets" { root.child("Dataset") } else { None }
+        .ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidXml))?;
+    let mut taxons: Vec<ImportedEntity> = dataset.children_named("TaxonNames")
+        .flat_map(|t| t.children_named("TaxonName"))
+        .filter_map(imported)
+        .collect();
+    apply_hierarchy(&mut taxons, &nodes_of(dataset, "TaxonHierarchies", "TaxonHierarchy", "Node"), "TaxonName");
+
+    let mut characters = Vec::new();
+    let mut states = Vec::new();
+    for c in dataset.children_named("Characters").flat_map(|c| c.children.iter()) {
+        let Some(mut character) = imported(c) else { continue };
+        for s in c.children_named("States").flat_map(|s| s.children_named("StateDefinition")) {
+            if let Some(state) = imported(s) {
+                character.states.push(state.id.clone());
+                states.push(state);
+            }
+        }
+        characters.push(character);
+    }
+    apply_hierarchy(&mut characters, &nodes_of(dataset, "CharacterTrees", "CharacterTree", "CharNode"), "Character");
+
+    for d in dataset.children_named("CodedDescriptions").flat_map(|d| d.children_named("CodedDescription")) {
+        let Some(taxon_id) = d.child("Scope").and_then(|s| ref_of(s, "TaxonName")) else { continue };
+        let Some(taxon) = taxons.iter_mut().find(|t| t.id == taxon_id) else { continue };
+        for c in d.child("SummaryData").iter().flat_map(|s| s.children_named("Categorical")) {
+            let Some(descriptor_id) = c.attr("ref") else { continue };
+            let states_ids = c.children_named("State").filter_map(|s| s.attr("ref")).map(String::from).collect();
+            taxon.descriptions.push((descriptor_id.to_string(), states_ids));
+        }
+    }
+
+    let books: Vec<ImportedEntity> = dataset.children_named("Publications")
+        .flat_map(|p| p.children_named("Publication"))
+        .filter_map(imported)
+        .collect();
+
+    let mut s = String::from("{\"taxons\":[");
+    for (i, t) in taxons.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        push_entity_start(&mut s, t, "detail");
+        push_string_list_field(&mut s, "path", &t.path);
+        push_string_list_field(&mut s, "children", &t.children);
+        if !t.descriptions.is_empty() {
+            s.push_str(",\"descriptions\":[");
+            for (j, (descriptor_id, states_ids)) in t.descriptions.iter().enumerate() {
+                if j > 0 { s.push(','); }
+                s.push_str(&format!("{{\"descriptorId\":{},\"statesIds\":", escape(descriptor_id)));
+                push_string_list(&mut s, states_ids);
+                s.push('}');
+            }
+            s.push(']');
+        }
+        s.push('}');
+    }
+    s.push_str("],\"characters\":[");
+    for (i, c) in characters.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        push_entity_start(&mut s, c, "detail");
+        push_string_list_field(&mut s, "path", &c.path);
+        push_string_list_field(&mut s, "children", &c.children);
+        push_string_list_field(&mut s, "states", &c.states);
+        s.push('}');
+    }
+    s.push_str("],\"states\":[");
+    for (i, st) in states.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        push_entity_start(&mut s, st, "description");
+        s.push('}');
+    }
+    s.push_str("],\"books\":[");
+    for (i, b) in books.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        s.push_str(&format!("{{\"id\":{},\"label\":{}}}", escape(&b.id), escape(&b.name)));
+    }
+    s.push_str("]}");
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+    use crate::jsondiff::json_value_to_string;
+
+    const DATASET: &str = r#"{
+        "id": "ds",
+        "taxons": [
+            {"id": "t0", "name": "Acanthaceae", "nameCN": "爵床科", "path": [], "children": ["t1"]},
+            {"id": "t1", "name": "Acanthus & co", "path": ["t0"], "children": [], "detail": "<p>Herbs</p>",
+             "descriptions": [{"descriptorId": "c1", "statesIds": ["s1"]}]},
+            {"id": "t2", "name": "Ficus", "path": [], "children": []}
+        ],
+        "characters": [
+            {"id": "c1", "name": "Color", "states": ["s1", "s2"]},
+            {"id": "c2", "name": "Size", "states": ["s3"]}
+        ],
+        "states": [{"id": "s1", "name": "Red"}, {"id": "s2", "name": "Blue"}, {"id": "s3", "name": "Big"}],
+        "books": [{"id": "b1", "label": "Flora"}]
+    }"#;
+
+    fn sdd(selection: Option<&EntitySelection>) -> String {
+        let value = parse_json(DATASET).unwrap();
+        let ds = Dataset::from_json(&value).unwrap();
+        dataset_to_sdd(&ds, selection, "2024-01-01T00:00:00Z")
+    }
+
+    #[test]
+    fn test_export_full_dataset() {
+        let xml = sdd(None);
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Datasets xmlns=\"http://rs.tdwg.org/UBIF/2006/\""));
+        assert!(xml.contains("<TechnicalMetadata created=\"2024-01-01T00:00:00Z\">"));
+        assert!(xml.contains("      <TaxonName id=\"t1\">\n        <Representation>\n          <Label>Acanthus &amp; co</Label>\n          <Detail>&lt;p&gt;Herbs&lt;/p&gt;</Detail>"));
+        assert!(xml.contains("<Label>Acanthaceae</Label>\n          <Label xml:lang=\"zh\">爵床科</Label>"));
+        assert!(!xml.contains("<Label xml:lang=\"en\">"));
+        assert!(xml.contains("<Node id=\"tn_t1\">\n            <Parent ref=\"tn_t0\"/>\n            <TaxonName ref=\"t1\"/>"));
+        assert!(xml.contains("<StateDefinition id=\"s2\">"));
+        assert!(xml.contains("<Categorical ref=\"c1\">\n            <State ref=\"s1\"/>"));
+        assert!(xml.contains("<Publication id=\"b1\">"));
+        assert!(xml.ends_with("  </Dataset>\n</Datasets>\n"));
+    }
+
+    #[test]
+    fn test_export_selection() {
+        let diff = parse_json(r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","name":{"old":"A","new":"B"}}]},"states":{"added":[{"id":"s3"}],"removed":[]}}"#).unwrap();
+        let selection = EntitySelection::from_diff(&diff);
+        assert!(selection.taxons.contains("t1") && selection.states.contains("s3"));
+        let xml = sdd(Some(&selection));
+        assert!(xml.contains("<TaxonName id=\"t0\">"));
+        assert!(xml.contains("<TaxonName id=\"t1\">"));
+        assert!(!xml.contains("<TaxonName id=\"t2\">"));
+        assert!(xml.contains("<CategoricalCharacter id=\"c1\">"));
+        assert!(xml.contains("<CategoricalCharacter id=\"c2\">"));
+        assert!(!xml.contains("<Publications>"));
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let json = sdd_to_hazo_json(&sdd(None)).unwrap();
+        let value = parse_json(&json).unwrap();
+        let ds = Dataset::from_json(&value).unwrap();
+        let t1 = ds.taxon("t1").unwrap();
+        assert_eq!(unescape(t1.name), "Acanthus & co");
+        assert_eq!(unescape(t1.detail), "<p>Herbs</p>");
+        assert_eq!(t1.path, vec!["t0"]);
+        assert_eq!(ds.taxon("t0").unwrap().children, vec!["t1"]);
+        assert_eq!(t1.descriptions[0].descriptor_id, "c1");
+        assert_eq!(t1.descriptions[0].states_ids, vec!["s1"]);
+        assert_eq!(ds.character("c1").unwrap().states, vec!["s1", "s2"]);
+        assert_eq!(ds.state("s3").unwrap().name, "Big");
+        assert_eq!(ds.book("b1").unwrap().label, "Flora");
+        assert_eq!(ds.taxon("t0").unwrap().name_cn, "爵床科");
+
+        // Les champs vides ou absents de l'export ne sont pas recréés
+        let t2 = value.get("taxons").and_then(|t| t.as_array()).unwrap()[2].clone();
+        assert_eq!(json_value_to_string(&t2), r#"{"id":"t2","name":"Ficus"}"#);
+        assert!(!json.contains("nameEN"));
+    }
+
+    #[test]
+    fn test_import_rejects_non_sdd() {
+        assert!(sdd_to_hazo_json("<Other/>").is_err());
+        assert!(sdd_to_hazo_json("{}").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum EditKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl EditKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EditKind::Equal => "equal",
+            EditKind::Insert => "insert",
+            EditKind::Delete => "delete",
+        }
+    }
+}
+
+// Plage d'éléments de l'ancienne et de la nouvelle séquence ; une insertion a une plage
+// `old` vide positionnée là où elle s'applique, et inversement pour une suppression.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Edit {
+    pub kind: EditKind,
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+// Ajoute `len` éléments de même nature à partir des positions `x` et `y`
+fn push_edit(edits: &mut Vec<Edit>, kind: EditKind, x: usize, y: usize, len: usize) {
+    if len == 0 {
+        return;
+    }
+    // Une suppression passe avant l'insertion qui la précède immédiatement
+    if kind == EditKind::Delete
+        && let Some(last) = edits.last()
+        && last.kind == EditKind::Insert
+        && last.old.end == x
+        && last.new.end == y
+        && let Some(insert) = edits.pop()
+    {
+        push_edit(edits, EditKind::Delete, x, insert.new.start, len);
+        push_edit(edits, EditKind::Insert, x + len, insert.new.start, insert.new.len());
+        return;
+    }
+    let (old_len, new_len) = match kind {
+        EditKind::Equal => (len, len),
+        EditKind::Insert => (0, len),
+        EditKind::Delete => (len, 0),
+    };
+    if let Some(last) = edits.last_mut()
+        && last.kind == kind
+        && last.old.end == x
+        && last.new.end == y
+    {
+        last.old.end += old_len;
+        last.new.end += new_len;
+        return;
+    }
+    edits.push(Edit { kind, old: x..x + old_len, new: y..y + new_len });
+}
+
+// Algorithme de Myers (« An O(ND) Difference Algorithm and Its Variations ») : plus court
+// script d'édition, regroupé en plages consécutives de même nature. La variante en espace
+// linéaire coupe les séquences au milieu du chemin et traite les deux moitiés à part.
+pub fn myers_diff<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    diff_range(a, b, 0, 0, &mut edits);
+    edits
+}
+
+// `x` et `y` : positions de `a` et `b` dans les séquences complètes
+fn diff_range<T: PartialEq>(a: &[T], b: &[T], x: usize, y: usize, edits: &mut Vec<Edit>) {
+    let prefix = a.iter().zip(b).take_while(|(p, q)| p == q).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(p, q)| p == q).count();
+    push_edit(edits, EditKind::Equal, x, y, prefix);
+    let (ma, mb) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let (x, y) = (x + prefix, y + prefix);
+    if ma.is_empty() || mb.is_empty() {
+        push_edit(edits, EditKind::Delete, x, y, ma.len());
+        push_edit(edits, EditKind::Insert, x + ma.len(), y, mb.len());
+    } else {
+        let (sx, sy) = middle_split(ma, mb);
+        diff_range(&ma[..sx], &mb[..sy], x, y, edits);
+        diff_range(&ma[sx..], &mb[sy..], x + sx, y + sy, edits);
+    }
+    push_edit(edits, EditKind::Equal, x + ma.len(), y + mb.len(), suffix);
+}
+
+// Point où se rejoignent les chemins partis du début et de la fin, avancés tour à tour
+// d'une différence ; seules les abscisses atteintes sur chaque diagonale sont gardées.
+// Les séquences sont non vides et diffèrent à leurs deux extrémités.
+fn middle_split<T: PartialEq>(a: &[T], b: &[T]) -> (usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d + 1;
+    let len = 2 * offset as usize + 1;
+    let mut forward = vec![-1isize; len];
+    let mut backward = vec![-1isize; len];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+    let delta = n - m;
+    // Avec une différence de longueurs impaire, les chemins se croisent pendant l'avancée du début
+    let odd = delta % 2 != 0;
+    // Diagonales sorties de la grille, à ne plus parcourir
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+    for d in 0..max_d {
+        for k in (-d + forward_start..=d - forward_end).step_by(2) {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[i - 1] < forward[i + 1]) { forward[i + 1] } else { forward[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if odd {
+                let j = offset + delta - k;
+                if (0..len as isize).contains(&j) && backward[j as usize] >= 0 && x >= n - backward[j as usize] {
+                    return (x as usize, y as usize);
+                }
+            }
+        }
+        for k in (-d + backward_start..=d - backward_end).step_by(2) {
+            let i = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && backward[i - 1] < backward[i + 1]) { backward[i + 1] } else { backward[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[i] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !odd {
+                let j = offset + delta - k;
+                if (0..len as isize).contains(&j) && forward[j as usize] >= 0 && forward[j as usize] >= n - x {
+                    let fx = forward[j as usize];
+                    return (fx as usize, (fx - (j - offset)) as usize);
+                }
+            }
+        }
+    }
+    (n as usize, 0)
+}
+
+// Positions d'une plus longue sous-suite strictement croissante (tri par patience, O(n log n))
+pub fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+    // tails[l] : position de la plus petite fin d'une sous-suite de longueur l + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![usize::MAX; values.len()];
+    for (i, &v) in values.iter().enumerate() {
+        let l = tails.partition_point(|&t| values[t] < v);
+        if l > 0 {
+            previous[i] = tails[l - 1];
+        }
+        if l == tails.len() {
+            tails.push(i);
+        } else {
+            tails[l] = i;
+        }
+    }
+    let mut result = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied().unwrap_or(usize::MAX);
+    while current != usize::MAX {
+        result.push(current);
+        current = previous[current];
+    }
+    result.reverse();
+    result
+}
+
+// Découpe en mots et blancs alternés : la concaténation des morceaux redonne le texte
+pub fn split_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+// Lignes avec leur fin de ligne
+pub fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(a: &str, b: &str) -> String {
+        let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+        let mut s = String::new();
+        for e in myers_diff(&a, &b) {
+            let (sign, chars) = match e.kind {
+                EditKind::Equal => ('=', &a[e.old]),
+                EditKind::Delete => ('-', &a[e.old]),
+                EditKind::Insert => ('+', &b[e.new]),
+            };
+            s.push(sign);
+            s.extend(chars);
+        }
+        s
+    }
+
+    #[test]
+    fn test_myers_diff() {
+        assert_eq!(script("ABCABBA", "CBABAC"), "-A+C=B-C=AB-B=A+C");
+        assert_eq!(script("same", "same"), "=same");
+        assert_eq!(script("", "abc"), "+abc");
+        assert_eq!(script("abc", ""), "-abc");
+        assert_eq!(script("kitten", "sitting"), "-k+s=itt-e+i=n+g");
+    }
+
+    #[test]
+    fn test_myers_diff_ranges() {
+        let edits = myers_diff(&[1, 2, 3, 4], &[1, 3, 4, 5]);
+        assert_eq!(edits, vec![
+            Edit { kind: EditKind::Equal, old: 0..1, new: 0..1 },
+            Edit { kind: EditKind::Delete, old: 1..2, new: 1..1 },
+            Edit { kind: EditKind::Equal, old: 2..4, new: 1..3 },
+            Edit { kind: EditKind::Insert, old: 4..4, new: 3..4 },
+        ]);
+    }
+
+    // Longueur du script minimal d'après la plus longue sous-suite commune
+    fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                lcs[i + 1][j + 1] = if a[i] == b[j] { lcs[i][j] + 1 } else { lcs[i][j + 1].max(lcs[i + 1][j]) };
+            }
+        }
+        a.len() + b.len() - 2 * lcs[a.len()][b.len()]
+    }
+
+    #[test]
+    fn test_myers_diff_minimal() {
+        let mut seed = 7u32;
+        let mut next = |modulo: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulo
+        };
+        for _ in 0..300 {
+            let a: Vec<u8> = (0..next(12)).map(|_| b'a' + next(3) as u8).collect();
+            let b: Vec<u8> = (0..next(12)).map(|_| b'a' + next(3) as u8).collect();
+            let edits = myers_diff(&a, &b);
+            let (mut x, mut y) = (0, 0);
+            for e in &edits {
+                assert_eq!((e.old.start, e.new.start), (x, y));
+                if e.kind == EditKind::Equal {
+                    assert_eq!(a[e.old.clone()], b[e.new.clone()]);
+                }
+                (x, y) = (e.old.end, e.new.end);
+            }
+            assert_eq!((x, y), (a.len(), b.len()));
+            let changed: usize = edits.iter().filter(|e| e.kind != EditKind::Equal).map(|e| e.old.len() + e.new.len()).sum();
+            assert_eq!(changed, edit_distance(&a, &b), "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_myers_diff_long_sequences() {
+        // Sans changement commun, le chemin complet compte 2 × 5000 étapes
+        let a: Vec<u32> = (0..5000).collect();
+        let b: Vec<u32> = (5000..10000).collect();
+        assert_eq!(myers_diff(&a, &b), vec![
+            Edit { kind: EditKind::Delete, old: 0..5000, new: 0..0 },
+            Edit { kind: EditKind::Insert, old: 5000..5000, new: 0..5000 },
+        ]);
+    }
+
+    #[test]
+    fn test_longest_increasing_subsequence() {
+        assert_eq!(longest_increasing_subsequence(&[2, 0, 1, 3]), vec![1, 2, 3]);
+        assert_eq!(longest_increasing_subsequence(&[3, 2, 1]).len(), 1);
+        assert!(longest_increasing_subsequence(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("  Herbs, rarely  shrubs\n"), vec!["  ", "Herbs,", " ", "rarely", "  ", "shrubs", "\n"]);
+        assert!(split_words("").is_empty());
+        assert_eq!(split_lines("a\nb\n\nc"), vec!["a\n", "b\n", "\n", "c"]);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::json::{escape, unescape, JsonValue};
+use crate::jsondiff::json_value_to_string;
+use crate::summary::{changed_fields, is_scalar_change};
+
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct CollectionStats {
+    pub name: String,
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub moved: usize,
+}
+
+impl CollectionStats {
+    pub fn changes(&self) -> usize {
+        self.added + self.removed + self.modified + self.moved
+    }
+}
+
+// Propriétés du jeu de données (clés racine hors collections)
+#[derive(Clone, Copy, Default, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct PropertyStats {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+}
+
+impl PropertyStats {
+    pub fn changes(&self) -> usize {
+        self.added + self.removed + self.modified
+    }
+}
+
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct DiffStats {
+    pub collections: Vec<CollectionStats>,
+    pub properties: PropertyStats,
+    // Nombre d'entités où chaque champ a changé, du plus fréquent au plus rare
+    pub fields: Vec<(String, usize)>,
+    // Taille JSON des valeurs supprimées, ajoutées ou remplacées
+    pub bytes_changed: usize,
+    // 1 pour des jeux identiques, 0 quand tout a changé
+    pub similarity: f64,
+}
+
+fn items<'v, 'a>(value: &'v JsonValue<'a>, name: &str) -> &'v [JsonValue<'a>] {
+    value.get(name).and_then(|v| v.as_array()).unwrap_or(&[])
+}
+
+fn object_keys<'a>(value: Option<&JsonValue<'a>>) -> Vec<&'a str> {
+    match value {
+        Some(JsonValue::Object(fields)) => fields.iter().map(|(k, _)| *k).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Octets touchés par une modification : anciennes et nouvelles valeurs des changements simples,
+// valeurs entières des éléments ajoutés ou supprimés ; les déplacements n'en comptent aucun
+fn change_bytes(change: &JsonValue) -> usize {
+    if is_scalar_change(change) {
+        return ["old", "new"].iter().filter_map(|k| change.get(k)).map(|v| json_value_to_string(v).len()).sum();
+    }
+    match change {
+        JsonValue::Object(fields) => fields.iter().map(|(k, v)| match *k {
+            "moved" | "renamed" => 0,
+            "added" | "removed" => match v {
+                JsonValue::Array(values) => values.iter().map(|v| json_value_to_string(v).len()).sum(),
+                JsonValue::Object(values) => values.iter().map(|(_, v)| json_value_to_string(v).len()).sum(),
+                _ => 0,
+            },
+            "inserted" | "deleted" => v.as_array().unwrap_or(&[]).iter().filter_map(|e| e.get("value")).map(|v| json_value_to_string(v).len()).sum(),
+            _ => change_bytes(v),
+        }).sum(),
+        JsonValue::Array(entries) => entries.iter().map(change_bytes).sum(),
+        _ => 0,
+    }
+}
+
+// Les noms viennent de clés JSON brutes et sont conservés déséchappés
+fn count_field(fields: &mut Vec<(String, usize)>, raw_name: &str) {
+    let name = unescape(raw_name);
+    match fields.iter_mut().find(|(f, _)| *f == name) {
+        Some((_, n)) => *n += 1,
+        None => fields.push((name.into_owned(), 1)),
+    }
+}
+
+// Statistiques d'un diff ; les tailles des deux jeux de données servent au taux de similarité
+pub fn diff_stats(diff: &JsonValue, old_size: usize, new_size: usize) -> DiffStats {
+    let mut stats = DiffStats::default();
+    let JsonValue::Object(collections) = diff else {
+        stats.similarity = 1.0;
+        return stats;
+    };
+    for (collection, value) in collections {
+        match *collection {
+            "unknown" => {}
+            "properties" => {
+                let (added, removed, modified, renamed) = (
+                    object_keys(value.get("added")),
+                    object_keys(value.get("removed")),
+                    object_keys(value.get("modified")),
+                    object_keys(value.get("renamed")),
+                );
+                for key in added.iter().chain(&removed).chain(&modified).chain(&renamed) {
+                    count_field(&mut stats.fields, key);
+                }
+                stats.bytes_changed += change_bytes(value);
+                stats.properties = PropertyStats {
+                    added: added.len(),
+                    removed: removed.len(),
+                    modified: modified.len() + renamed.len(),
+                };
+            }
+            _ => {
+                let c = CollectionStats {
+                    name: unescape(collection).into_owned(),
+                    added: items(value, "added").len(),
+                    removed: items(value, "removed").len(),
+                    modified: items(value, "modified").len(),
+                    moved: items(value, "moved").len(),
+                };
+                if c.changes() == 0 {
+                    continue;
+                }
+                for entry in items(value, "modified") {
+                    for field in changed_fields(entry) {
+                        count_field(&mut stats.fields, field);
+                    }
+                }
+                stats.bytes_changed += change_bytes(value);
+                stats.collections.push(c);
+            }
+        }
+    }
+    stats.fields.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let total = old_size + new_size;
+    stats.similarity = if total == 0 { 1.0 } else { 1.0 - (stats.bytes_changed as f64 / total as f64).min(1.0) };
+    stats
+}
+
+const STAT_BAR_WIDTH: usize = 40;
+
+impl DiffStats {
+    pub fn has_changes(&self) -> bool {
+        !self.collections.is_empty() || self.properties.changes() > 0
+    }
+
+    pub fn to_json(&self) -> String {
+        let collections: Vec<String> = self.collections.iter().map(|c| format!(
+            "{}:{{\"added\":{},\"removed\":{},\"modified\":{},\"moved\":{}}}",
+            escape(&c.name), c.added, c.removed, c.modified, c.moved,
+        )).collect();
+        let fields: Vec<String> = self.fields.iter().map(|(f, n)| format!("{}:{}", escape(f), n)).collect();
+        let p = &self.properties;
+        format!(
+            "{{\"collections\":{{{}}},\"properties\":{{\"added\":{},\"removed\":{},\"modified\":{}}},\"fields\":{{{}}},\"bytesChanged\":{},\"similarity\":{}}}",
+            collections.join(","), p.added, p.removed, p.modified, fields.join(","), self.bytes_changed, self.similarity,
+        )
+    }
+
+    // Présentation à la `git diff --stat` : une barre par collection (+ ajouts, - suppressions,
+    // ~ modifications, > déplacements), réduite si la plus grande dépasse la largeur
+    pub fn to_text(&self) -> String {
+        let width = self.collections.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let largest = self.collections.iter().map(CollectionStats::changes).max().unwrap_or(0);
+        let scale = |n: usize| if largest <= STAT_BAR_WIDTH { n } else { (n * STAT_BAR_WIDTH).div_ceil(largest) };
+        let mut s = String::new();
+        for c in &self.collections {
+            let bar: String = [(c.added, '+'), (c.removed, '-'), (c.modified, '~'), (c.moved, '>')]
+                .iter()
+                .flat_map(|&(n, sign)| std::iter::repeat_n(sign, scale(n)))
+                .collect();
+            s.push_str(&format!(" {:<width$} | {:>5} {}\n", c.name, c.changes(), bar, width = width));
+        }
+        let total = |f: fn(&CollectionStats) -> usize| self.collections.iter().map(f).sum::<usize>();
+        s.push_str(&format!(
+            " {} collection{} changed, {} added, {} removed, {} modified, {} moved\n",
+            self.collections.len(),
+            if self.collections.len() == 1 { "" } else { "s" },
+            total(|c| c.added),
+            total(|c| c.removed),
+            total(|c| c.modified),
+            total(|c| c.moved),
+        ));
+        let p = &self.properties;
+        if p.changes() > 0 {
+            s.push_str(&format!(" dataset properties: {} added, {} removed, {} modified\n", p.added, p.removed, p.modified));
+        }
+        if !self.fields.is_empty() {
+            let fields: Vec<String> = self.fields.iter().map(|(f, n)| format!("{} ({})", f, n)).collect();
+            s.push_str(&format!(" fields: {}\n", fields.join(", ")));
+        }
+        s.push_str(&format!(" {} bytes changed, {:.1}% similar\n", self.bytes_changed, self.similarity * 100.0));
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    #[test]
+    fn test_diff_stats() {
+        let diff = parse_json(r#"{
+            "taxons":{"added":[{"id":"t9"}],"removed":[],"modified":[{"id":"t1","name":{"old":"A","new":"B"},"added":{"nameEN":"b"}},{"id":"t2","name":{"old":"C","new":"D"}}],"moved":[{"id":"t4","from":"t1","to":null,"descendants":[]}]},
+            "books":{"added":[],"removed":[]},
+            "properties":{"modified":{"id":{"old":"d1","new":"d2"}}}
+        }"#).unwrap();
+        let stats = diff_stats(&diff, 500, 500);
+        assert_eq!(stats.collections, vec![
+            CollectionStats { name: "taxons".to_string(), added: 1, removed: 0, modified: 2, moved: 1 },
+        ]);
+        assert_eq!(stats.properties, PropertyStats { added: 0, removed: 0, modified: 1 });
+        assert_eq!(stats.fields, vec![("name".to_string(), 2), ("id".to_string(), 1), ("nameEN".to_string(), 1)]);
+        assert_eq!(stats.bytes_changed, 11 + 6 + 3 + 6 + 8);
+        assert!((stats.similarity - 0.966).abs() < 1e-9);
+        assert_eq!(stats.to_json(), concat!(
+            r#"{"collections":{"taxons":{"added":1,"removed":0,"modified":2,"moved":1}},"properties":{"added":0,"removed":0,"modified":1},"#,
+            r#""fields":{"name":2,"id":1,"nameEN":1},"bytesChanged":34,"similarity":0.966}"#,
+        ));
+        assert_eq!(stats.to_text(), concat!(
+            " taxons |     4 +~~>\n",
+            " 1 collection changed, 1 added, 0 removed, 2 modified, 1 moved\n",
+            " dataset properties: 0 added, 0 removed, 1 modified\n",
+            " fields: name (2), id (1), nameEN (1)\n",
+            " 34 bytes changed, 96.6% similar\n",
+        ));
+        assert_eq!(diff_stats(&JsonValue::Object(Vec::new()), 10, 10).similarity, 1.0);
+    }
+
+    #[test]
+    fn test_diff_stats_escapes_names() {
+        let diff = parse_json(r#"{"a\"b":{"added":[],"removed":[],"modified":[{"id":"x","q\\r":{"old":1,"new":2}}]}}"#).unwrap();
+        let stats = diff_stats(&diff, 100, 100);
+        assert_eq!(stats.collections[0].name, "a\"b");
+        assert_eq!(stats.fields, vec![("q\\r".to_string(), 1)]);
+        let json = stats.to_json();
+        assert!(json.starts_with(r#"{"collections":{"a\"b":{"added":0,"removed":0,"modified":1,"moved":0}},"properties":{"added":0,"removed":0,"modified":0},"fields":{"q\\r":1}"#));
+        assert!(parse_json(&json).is_ok());
+    }
+}
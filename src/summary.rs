@@ -0,0 +1,653 @@
+use std::borrow::Cow;
+
+use crate::json::{escape, unescape, JsonValue};
+use crate::jsondiff::json_value_to_string;
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum SummaryFormat {
+    Text,
+    Markdown,
+    Json,
+}
+
+impl SummaryFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(SummaryFormat::Text),
+            "markdown" => Some(SummaryFormat::Markdown),
+            "json" => Some(SummaryFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum DomainEvent<'a> {
+    Added { collection: &'a str, id: Cow<'a, str>, name: Option<Cow<'a, str>> },
+    Removed { collection: &'a str, id: Cow<'a, str>, name: Option<Cow<'a, str>> },
+    Renamed { collection: &'a str, id: Cow<'a, str>, old: Cow<'a, str>, new: Cow<'a, str> },
+    // `old` et `new` gardent la valeur JSON d'origine, pour conserver son type
+    FieldChanged { collection: &'a str, id: Cow<'a, str>, field: &'a str, old: Option<JsonValue<'a>>, new: Option<JsonValue<'a>> },
+    ReferenceAdded { collection: &'a str, id: Cow<'a, str>, field: &'a str, target: Cow<'a, str> },
+    ReferenceRemoved { collection: &'a str, id: Cow<'a, str>, field: &'a str, target: Cow<'a, str> },
+    Moved { collection: &'a str, id: Cow<'a, str>, from: Option<Cow<'a, str>>, to: Option<Cow<'a, str>>, descendants: usize },
+    // Entité ajoutée appariée à une entité supprimée d'identifiant différent
+    Replaced { collection: &'a str, id: Cow<'a, str>, old_id: Cow<'a, str> },
+    // Même clé renommée dans `count` entités de la collection
+    FieldRenamed { collection: &'a str, old: &'a str, new: Cow<'a, str>, count: usize },
+    TypeChanged { collection: &'a str, id: Cow<'a, str>, field: &'a str, old_type: Cow<'a, str>, new_type: Cow<'a, str> },
+    Reordered { collection: &'a str, id: Cow<'a, str>, from: usize, to: usize },
+    ReferenceReordered { collection: &'a str, id: Cow<'a, str>, field: &'a str, target: Cow<'a, str>, from: usize, to: usize },
+    PropertyChanged { key: &'a str, old: Option<JsonValue<'a>>, new: Option<JsonValue<'a>> },
+}
+
+impl DomainEvent<'_> {
+    pub fn collection(&self) -> &str {
+        match self {
+            DomainEvent::Added { collection, .. }
+            | DomainEvent::Removed { collection, .. }
+            | DomainEvent::Renamed { collection, .. }
+            | DomainEvent::FieldChanged { collection, .. }
+            | DomainEvent::ReferenceAdded { collection, .. }
+            | DomainEvent::ReferenceRemoved { collection, .. }
+            | DomainEvent::Moved { collection, .. }
+            | DomainEvent::Replaced { collection, .. }
+            | DomainEvent::FieldRenamed { collection, .. }
+            | DomainEvent::TypeChanged { collection, .. }
+            | DomainEvent::Reordered { collection, .. }
+            | DomainEvent::ReferenceReordered { collection, .. } => collection,
+            DomainEvent::PropertyChanged { .. } => "properties",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::Added { .. } => "added",
+            DomainEvent::Removed { .. } => "removed",
+            DomainEvent::Renamed { .. } => "renamed",
+            DomainEvent::FieldChanged { .. } => "fieldChanged",
+            DomainEvent::ReferenceAdded { .. } => "referenceAdded",
+            DomainEvent::ReferenceRemoved { .. } => "referenceRemoved",
+            DomainEvent::Moved { .. } => "moved",
+            DomainEvent::Replaced { .. } => "replaced",
+            DomainEvent::FieldRenamed { .. } => "fieldRenamed",
+            DomainEvent::TypeChanged { .. } => "typeChanged",
+            DomainEvent::Reordered { .. } => "reordered",
+            DomainEvent::ReferenceReordered { .. } => "referenceReordered",
+            DomainEvent::PropertyChanged { .. } => "propertyChanged",
+        }
+    }
+}
+
+pub fn entity_label(collection: &str) -> &str {
+    match collection {
+        "taxons" => "Taxon",
+        "characters" => "Character",
+        "states" => "State",
+        "books" => "Book",
+        "properties" => "Dataset",
+        other => other,
+    }
+}
+
+pub fn collection_label(collection: &str) -> &str {
+    match collection {
+        "taxons" => "Taxa",
+        "characters" => "Characters",
+        "states" => "States",
+        "books" => "Books",
+        "properties" => "Dataset",
+        other => other,
+    }
+}
+
+fn reference_label(field: &str) -> &str {
+    match field {
+        "states" => "state",
+        "children" => "child",
+        "path" => "ancestor",
+        "inapplicableStatesIds" => "inapplicable state",
+        "requiredStatesIds" => "required state",
+        "statesIds" => "state",
+        other => other,
+    }
+}
+
+impl std::fmt::Display for DomainEvent<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DomainEvent::Added { collection, id, name } | DomainEvent::Removed { collection, id, name } => {
+                write!(f, "{} {}", entity_label(collection), id)?;
+                if let Some(name) = name {
+                    write!(f, " ({})", name)?;
+                }
+                write!(f, " {}", self.name())
+            }
+            DomainEvent::Renamed { collection, id, old, new } => {
+                write!(f, "{} {} renamed from {} to {}", entity_label(collection), id, old, new)
+            }
+            DomainEvent::FieldChanged { collection, id, field, old, new } => {
+                write!(f, "{} {} ", entity_label(collection), id)?;
+                write_change(f, field, old, new)
+            }
+            DomainEvent::ReferenceAdded { collection, id, field, target } => {
+                write!(f, "{} {} gained {} {}", entity_label(collection), id, reference_label(field), target)
+            }
+            DomainEvent::ReferenceRemoved { collection, id, field, target } => {
+                write!(f, "{} {} lost {} {}", entity_label(collection), id, reference_label(field), target)
+            }
+            DomainEvent::Moved { collection, id, from, to, descendants } => {
+                write!(f, "{} {} moved from {} to {}", entity_label(collection), id, from.as_deref().unwrap_or("the root"), to.as_deref().unwrap_or("the root"))?;
+                match descendants {
+                    0 => Ok(()),
+                    1 => write!(f, " with 1 descendant"),
+                    n => write!(f, " with {} descendants", n),
+                }
+            }
+            DomainEvent::Replaced { collection, id, old_id } => {
+                write!(f, "{} {} replaces {}", entity_label(collection), id, old_id)
+            }
+            DomainEvent::FieldRenamed { collection: "properties", old, new, .. } => {
+                write!(f, "Dataset property {} renamed to {}", old, new)
+            }
+            DomainEvent::FieldRenamed { collection, old, new, count } => {
+                write!(f, "{} field {} renamed to {} in {} {}", entity_label(collection), old, new, count, if *count == 1 { "entity" } else { "entities" })
+            }
+            DomainEvent::TypeChanged { collection, id, field, old_type, new_type } => {
+                write!(f, "{} {} {} changed type from {} to {}", entity_label(collection), id, field, old_type, new_type)
+            }
+            DomainEvent::Reordered { collection, id, from, to } => {
+                write!(f, "{} {} reordered from position {} to {}", entity_label(collection), id, from, to)
+            }
+            DomainEvent::ReferenceReordered { collection, id, field, target, from, to } => {
+                write!(f, "{} {} reordered {} {} from position {} to {}", entity_label(collection), id, reference_label(field), target, from, to)
+            }
+            DomainEvent::PropertyChanged { key, old, new } => {
+                write!(f, "Dataset ")?;
+                write_change(f, key, old, new)
+            }
+        }
+    }
+}
+
+fn write_change(f: &mut std::fmt::Formatter<'_>, field: &str, old: &Option<JsonValue>, new: &Option<JsonValue>) -> std::fmt::Result {
+    let text = |v: &Option<JsonValue>| v.as_ref().and_then(scalar_text).map(Cow::into_owned);
+    match (text(old), text(new)) {
+        (Some(old), Some(new)) => write!(f, "{} changed from {} to {}", field, old, new),
+        (None, Some(new)) => write!(f, "{} set to {}", field, new),
+        (Some(old), None) => write!(f, "{} cleared (was {})", field, old),
+        (None, None) => write!(f, "{} changed", field),
+    }
+}
+
+fn scalar<'a>(v: &JsonValue<'a>) -> Option<JsonValue<'a>> {
+    match v {
+        JsonValue::Array(_) | JsonValue::Object(_) => None,
+        v => Some(v.clone()),
+    }
+}
+
+fn scalar_text<'a>(v: &JsonValue<'a>) -> Option<Cow<'a, str>> {
+    match v {
+        JsonValue::String(s) => Some(unescape(s)),
+        JsonValue::Number(n) => Some(Cow::Owned(n.to_string())),
+        JsonValue::Boolean(b) => Some(Cow::Owned(b.to_string())),
+        JsonValue::Null => Some(Cow::Borrowed("null")),
+        _ => None,
+    }
+}
+
+pub(crate) fn entity_id<'a>(entity: &JsonValue<'a>) -> Cow<'a, str> {
+    match entity.get("id") {
+        Some(id) => scalar_text(id).unwrap_or_else(|| Cow::Owned(json_value_to_string(id))),
+        None => Cow::Borrowed("?"),
+    }
+}
+
+// Champs modifiés d'une entrée `modified` ; un changement d'identifiant compte pour `id`,
+// l'indice d'un élément comparé par position n'en est pas un
+pub(crate) fn changed_fields<'a>(entry: &JsonValue<'a>) -> Vec<&'a str> {
+    let mut fields = Vec::new();
+    if let JsonValue::Object(entries) = entry {
+        for (field, change) in entries {
+            match (*field, change) {
+                ("id", _) => {}
+                ("index", _) if entry.get("id").is_none() => {}
+                ("replaces", _) => fields.push("id"),
+                ("added" | "removed" | "renamed", JsonValue::Object(sub)) => fields.extend(sub.iter().map(|(k, _)| *k)),
+                _ => fields.push(*field),
+            }
+        }
+    }
+    fields
+}
+
+fn entity_name<'a>(entity: &JsonValue<'a>) -> Option<Cow<'a, str>> {
+    entity.get("name").or_else(|| entity.get("label")).and_then(|v| v.as_str()).map(unescape)
+}
+
+fn fields<'v, 'a>(v: Option<&'v JsonValue<'a>>) -> &'v [(&'a str, JsonValue<'a>)] {
+    match v {
+        Some(JsonValue::Object(fields)) => fields,
+        _ => &[],
+    }
+}
+
+// Changement de valeur simple, éventuellement accompagné de ses morceaux de diff textuel
+pub(crate) fn is_scalar_change(v: &JsonValue) -> bool {
+    matches!(v, JsonValue::Object(f) if v.get("old").is_some() && v.get("new").is_some()
+        && f.iter().all(|(k, _)| matches!(*k, "old" | "new" | "hunks" | "oldType" | "newType")))
+}
+
+// Déplacement dans l'ordre d'un tableau, par opposition à un déplacement dans la hiérarchie
+pub(crate) fn index_move(m: &JsonValue) -> Option<(usize, usize)> {
+    match (m.get("fromIndex")?, m.get("toIndex")?) {
+        (JsonValue::Number(from), JsonValue::Number(to)) => Some((*from as usize, *to as usize)),
+        _ => None,
+    }
+}
+
+// Éléments gagnés et perdus d'un tableau de chaînes d'après son diff :
+// les positions inchangées étant identiques des deux côtés, seules les valeurs
+// présentes dans le diff interviennent.
+fn reference_changes<'a>(change: &JsonValue<'a>) -> Option<(Vec<&'a str>, Vec<&'a str>)> {
+    let mut olds = Vec::new();
+    let mut news = Vec::new();
+    if let (Some(inserted), Some(deleted)) = (change.get("inserted"), change.get("deleted")) {
+        for v in deleted.as_array()? {
+            olds.push(v.get("value")?.as_str()?);
+        }
+        for v in inserted.as_array()? {
+            news.push(v.get("value")?.as_str()?);
+        }
+        let gained = news.iter().filter(|n| !olds.contains(n)).copied().collect();
+        let lost = olds.iter().filter(|o| !news.contains(o)).copied().collect();
+        return Some((gained, lost));
+    }
+    for v in change.get("removed")?.as_array()? {
+        olds.push(v.as_str()?);
+    }
+    for v in change.get("added")?.as_array()? {
+        news.push(v.as_str()?);
+    }
+    for m in change.get("modified").and_then(|m| m.as_array()).unwrap_or(&[]) {
+        olds.push(m.get("old")?.as_str()?);
+        news.push(m.get("new")?.as_str()?);
+    }
+    let gained = news.iter().filter(|n| !olds.contains(n)).copied().collect();
+    let lost = olds.iter().filter(|o| !news.contains(o)).copied().collect();
+    Some((gained, lost))
+}
+
+fn summarize_entity<'a>(collection: &'a str, entry: &JsonValue<'a>, events: &mut Vec<DomainEvent<'a>>) {
+    let id = entity_id(entry);
+    for (field, change) in fields(Some(entry)) {
+        match *field {
+            // Les renommages de clés sont regroupés pour toute la collection
+            "id" | "renamed" => {}
+            "replaces" => events.push(DomainEvent::Replaced { collection, id: id.clone(), old_id: scalar_text(change).unwrap_or_default() }),
+            "added" | "removed" => {
+                for (f, v) in fields(Some(change)) {
+                    let (old, new) = if *field == "added" { (None, scalar(v)) } else { (scalar(v), None) };
+                    events.push(DomainEvent::FieldChanged { collection, id: id.clone(), field: f, old, new });
+                }
+            }
+            "name" | "label" if is_scalar_change(change) && change.get("old").and_then(|v| v.as_str()).is_some() && change.get("new").and_then(|v| v.as_str()).is_some() => {
+                events.push(DomainEvent::Renamed {
+                    collection,
+                    id: id.clone(),
+                    old: change.get("old").and_then(scalar_text).unwrap_or_default(),
+                    new: change.get("new").and_then(scalar_text).unwrap_or_default(),
+                });
+            }
+            _ if is_scalar_change(change) && change.get("oldType").is_some() => {
+                events.push(DomainEvent::TypeChanged {
+                    collection,
+                    id: id.clone(),
+                    field,
+                    old_type: change.get("oldType").and_then(scalar_text).unwrap_or_default(),
+                    new_type: change.get("newType").and_then(scalar_text).unwrap_or_default(),
+                });
+            }
+            _ if is_scalar_change(change) => {
+                events.push(DomainEvent::FieldChanged {
+                    collection,
+                    id: id.clone(),
+                    field,
+                    old: change.get("old").and_then(scalar),
+                    new: change.get("new").and_then(scalar),
+                });
+            }
+            _ => match reference_changes(change) {
+                Some((gained, lost)) if !gained.is_empty() || !lost.is_empty() || change.get("moved").is_some() => {
+                    for target in gained {
+                        events.push(DomainEvent::ReferenceAdded { collection, id: id.clone(), field, target: unescape(target) });
+                    }
+                    for target in lost {
+                        events.push(DomainEvent::ReferenceRemoved { collection, id: id.clone(), field, target: unescape(target) });
+                    }
+                    for m in change.get("moved").and_then(|v| v.as_array()).unwrap_or(&[]) {
+                        if let (Some(target), Some((from, to))) = (m.get("value").and_then(scalar_text), index_move(m)) {
+                            events.push(DomainEvent::ReferenceReordered { collection, id: id.clone(), field, target, from, to });
+                        }
+                    }
+                }
+                _ => events.push(DomainEvent::FieldChanged { collection, id: id.clone(), field, old: None, new: None }),
+            },
+        }
+    }
+}
+
+pub fn summarize<'a>(diff: &JsonValue<'a>) -> Vec<DomainEvent<'a>> {
+    let mut events = Vec::new();
+    for (collection, value) in fields(Some(diff)) {
+        match *collection {
+            "unknown" => {}
+            "properties" => {
+                for (key, v) in fields(value.get("added")) {
+                    events.push(DomainEvent::PropertyChanged { key, old: None, new: scalar(v) });
+                }
+                for (key, v) in fields(value.get("removed")) {
+                    events.push(DomainEvent::PropertyChanged { key, old: scalar(v), new: None });
+                }
+                for (key, v) in fields(value.get("modified")) {
+                    let (old, new) = if is_scalar_change(v) {
+                        (v.get("old").and_then(scalar), v.get("new").and_then(scalar))
+                    } else {
+                        (None, None)
+                    };
+                    events.push(DomainEvent::PropertyChanged { key, old, new });
+                }
+                for (old, new) in fields(value.get("renamed")) {
+                    events.push(DomainEvent::FieldRenamed { collection, old, new: scalar_text(new).unwrap_or_default(), count: 1 });
+                }
+            }
+            _ => {
+                for entity in value.get("added").and_then(|v| v.as_array()).unwrap_or(&[]) {
+                    events.push(DomainEvent::Added { collection, id: entity_id(entity), name: entity_name(entity) });
+                }
+                for entity in value.get("removed").and_then(|v| v.as_array()).unwrap_or(&[]) {
+                    events.push(DomainEvent::Removed { collection, id: entity_id(entity), name: entity_name(entity) });
+                }
+                for m in value.get("moved").and_then(|v| v.as_array()).unwrap_or(&[]) {
+                    if let Some((from, to)) = index_move(m) {
+                        events.push(DomainEvent::Reordered { collection, id: entity_id(m), from, to });
+                        continue;
+                    }
+                    events.push(DomainEvent::Moved {
+                        collection,
+                        id: entity_id(m),
+                        from: m.get("from").and_then(|v| v.as_str()).map(unescape),
+                        to: m.get("to").and_then(|v| v.as_str()).map(unescape),
+                        descendants: m.get("descendants").and_then(|v| v.as_array()).map_or(0, |d| d.len()),
+                    });
+                }
+                let mut renames: Vec<(&str, Cow<str>, usize)> = Vec::new();
+                for entry in value.get("modified").and_then(|v| v.as_array()).unwrap_or(&[]) {
+                    summarize_entity(collection, entry, &mut events);
+                    for (old, new) in fields(entry.get("renamed")) {
+                        let new = scalar_text(new).unwrap_or_default();
+                        match renames.iter_mut().find(|(o, n, _)| o == old && *n == new) {
+                            Some((_, _, count)) => *count += 1,
+                            None => renames.push((old, new, 1)),
+                        }
+                    }
+                }
+                for (old, new, count) in renames {
+                    events.push(DomainEvent::FieldRenamed { collection, old, new, count });
+                }
+            }
+        }
+    }
+    events
+}
+
+pub fn render_summary(events: &[DomainEvent], format: SummaryFormat) -> String {
+    match format {
+        SummaryFormat::Text => {
+            let mut s = String::new();
+            for e in events {
+                s.push_str(&e.to_string());
+                s.push('\n');
+            }
+            s
+        }
+        SummaryFormat::Markdown => render_markdown(events),
+        SummaryFormat::Json => render_json(events),
+    }
+}
+
+pub fn escape_markdown(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s
+}
+
+fn render_markdown(events: &[DomainEvent]) -> String {
+    let mut collections: Vec<&str> = Vec::new();
+    for e in events {
+        if !collections.contains(&e.collection()) {
+            collections.push(e.collection());
+        }
+    }
+    let mut s = String::new();
+    for collection in collections {
+        if !s.is_empty() {
+            s.push('\n');
+        }
+        s.push_str("## ");
+        s.push_str(&escape_markdown(collection_label(collection)));
+        s.push_str("\n\n");
+        for e in events.iter().filter(|e| e.collection() == collection) {
+            s.push_str("- ");
+            s.push_str(&escape_markdown(&e.to_string()));
+            s.push('\n');
+        }
+    }
+    s
+}
+
+fn render_json(events: &[DomainEvent]) -> String {
+    let mut s = String::from("[");
+    for (i, e) in events.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        s.push_str("{\"event\":\"");
+        s.push_str(e.name());
+        s.push_str("\",\"collection\":");
+        s.push_str(&escape(&unescape(e.collection())));
+        // Les textes sont déjà déséchappés ; les noms de champs et de collections sont des clés JSON brutes
+        let mut value = |key: &str, json: Option<String>| {
+            if let Some(json) = json {
+                s.push_str(",\"");
+                s.push_str(key);
+                s.push_str("\":");
+                s.push_str(&json);
+            }
+        };
+        let text = |v: Option<&str>| v.map(escape);
+        let raw_key = |k: &str| Some(escape(&unescape(k)));
+        let json = |v: &Option<JsonValue>| v.as_ref().map(json_value_to_string);
+        match e {
+            DomainEvent::Added { id, name, .. } | DomainEvent::Removed { id, name, .. } => {
+                value("id", text(Some(id)));
+                value("name", text(name.as_deref()));
+            }
+            DomainEvent::Renamed { id, old, new, .. } => {
+                value("id", text(Some(id)));
+                value("old", text(Some(old)));
+                value("new", text(Some(new)));
+            }
+            DomainEvent::FieldChanged { id, field: f, old, new, .. } => {
+                value("id", text(Some(id)));
+                value("field", raw_key(f));
+                value("old", json(old));
+                value("new", json(new));
+            }
+            DomainEvent::ReferenceAdded { id, field: f, target, .. } | DomainEvent::ReferenceRemoved { id, field: f, target, .. } => {
+                value("id", text(Some(id)));
+                value("field", raw_key(f));
+                value("target", text(Some(target)));
+            }
+            DomainEvent::Moved { id, from, to, descendants, .. } => {
+                value("id", text(Some(id)));
+                value("from", text(from.as_deref()));
+                value("to", text(to.as_deref()));
+                value("descendants", Some(descendants.to_string()));
+            }
+            DomainEvent::Replaced { id, old_id, .. } => {
+                value("id", text(Some(id)));
+                value("oldId", text(Some(old_id)));
+            }
+            DomainEvent::FieldRenamed { old, new, count, .. } => {
+                value("old", raw_key(old));
+                value("new", text(Some(new)));
+                value("count", Some(count.to_string()));
+            }
+            DomainEvent::TypeChanged { id, field: f, old_type, new_type, .. } => {
+                value("id", text(Some(id)));
+                value("field", raw_key(f));
+                value("oldType", text(Some(old_type)));
+                value("newType", text(Some(new_type)));
+            }
+            DomainEvent::Reordered { id, from, to, .. } => {
+                value("id", text(Some(id)));
+                value("fromIndex", Some(from.to_string()));
+                value("toIndex", Some(to.to_string()));
+            }
+            DomainEvent::ReferenceReordered { id, field: f, target, from, to, .. } => {
+                value("id", text(Some(id)));
+                value("field", raw_key(f));
+                value("target", text(Some(target)));
+                value("fromIndex", Some(from.to_string()));
+                value("toIndex", Some(to.to_string()));
+            }
+            DomainEvent::PropertyChanged { key, old, new } => {
+                value("key", raw_key(key));
+                value("old", json(old));
+                value("new", json(new));
+            }
+        }
+        s.push('}');
+    }
+    s.push(']');
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_json;
+
+    const DIFF: &str = r#"{
+        "taxons":{"added":[],"removed":[],"modified":[{"id":"t12","name":{"old":"Acanthaceae","new":"Acanthaceae s.l."},"added":{"nameEN":"Acanthus family"}}],
+                  "moved":[{"id":"t3","from":"t1","to":null,"descendants":["t4","t5"]}]},
+        "characters":{"added":[],"removed":[],"modified":[{"id":"c3","states":{"added":["s9"],"removed":[],"modified":[{"old":"s2","new":"s4"}]}}]},
+        "states":{"added":[{"id":"s9","name":"Blue"}],"removed":[]},
+        "books":{"added":[],"removed":[{"id":"b2","label":"Flora"}]},
+        "properties":{"modified":{"id":{"old":"ds1","new":"ds2"}}},
+        "unknown":["extra"]
+    }"#;
+
+    fn sentences(diff: &str) -> Vec<String> {
+        let value = parse_json(diff).unwrap();
+        summarize(&value).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_summarize_events() {
+        assert_eq!(sentences(DIFF), vec![
+            "Taxon t3 moved from t1 to the root with 2 descendants",
+            "Taxon t12 renamed from Acanthaceae to Acanthaceae s.l.",
+            "Taxon t12 nameEN set to Acanthus family",
+            "Character c3 gained state s9",
+            "Character c3 gained state s4",
+            "Character c3 lost state s2",
+            "State s9 (Blue) added",
+            "Book b2 (Flora) removed",
+            "Dataset id changed from ds1 to ds2",
+        ]);
+    }
+
+    #[test]
+    fn test_summarize_sequence_changes() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","children":{"inserted":[{"index":1,"value":"t5"}],"deleted":[{"index":3,"value":"t9"}]}}]}}"#;
+        assert_eq!(sentences(diff), vec!["Taxon t1 gained child t5", "Taxon t1 lost child t9"]);
+    }
+
+    #[test]
+    fn test_summarize_replacement() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"n1","replaces":"t1","detail":{"old":"Fig","new":"Common fig"}}]}}"#;
+        assert_eq!(sentences(diff), vec!["Taxon n1 replaces t1", "Taxon n1 detail changed from Fig to Common fig"]);
+    }
+
+    #[test]
+    fn test_summarize_field_renames() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[
+            {"id":"t1","renamed":{"vernacularName":"vernacular"}},
+            {"id":"t2","renamed":{"vernacularName":"vernacular"},"name":{"old":"A","new":"B"}},
+            {"id":"t3","renamed":{"nameFR":"nameFr"}}
+        ]},"properties":{"renamed":{"editor":"author"}}}"#;
+        assert_eq!(sentences(diff), vec![
+            "Taxon t2 renamed from A to B",
+            "Taxon field vernacularName renamed to vernacular in 2 entities",
+            "Taxon field nameFR renamed to nameFr in 1 entity",
+            "Dataset property editor renamed to author",
+        ]);
+    }
+
+    #[test]
+    fn test_summarize_type_change() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","height":{"old":12,"new":"12","oldType":"number","newType":"string"}}]}}"#;
+        assert_eq!(sentences(diff), vec!["Taxon t1 height changed type from number to string"]);
+    }
+
+    #[test]
+    fn test_summarize_reordered() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","children":{"added":[],"removed":[],"moved":[{"value":"t5","fromIndex":2,"toIndex":0}]}}],"moved":[{"id":"t4","fromIndex":0,"toIndex":3}]}}"#;
+        assert_eq!(sentences(diff), vec!["Taxon t4 reordered from position 0 to 3", "Taxon t1 reordered child t5 from position 2 to 0"]);
+    }
+
+    #[test]
+    fn test_summarize_unescapes_values() {
+        let diff = r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","detail":{"old":"a \"b\"","new":"c"}}]}}"#;
+        assert_eq!(sentences(diff), vec!["Taxon t1 detail changed from a \"b\" to c"]);
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let value = parse_json(DIFF).unwrap();
+        let events = summarize(&value);
+        let md = render_summary(&events[6..], SummaryFormat::Markdown);
+        assert_eq!(md, "## States\n\n- State s9 (Blue) added\n\n## Books\n\n- Book b2 (Flora) removed\n\n## Dataset\n\n- Dataset id changed from ds1 to ds2\n");
+    }
+
+    #[test]
+    fn test_render_json() {
+        let value = parse_json(DIFF).unwrap();
+        let events = summarize(&value);
+        let json = render_summary(&events[..2], SummaryFormat::Json);
+        assert_eq!(json, concat!(
+            r#"[{"event":"moved","collection":"taxons","id":"t3","from":"t1","descendants":2},"#,
+            r#"{"event":"renamed","collection":"taxons","id":"t12","old":"Acanthaceae","new":"Acanthaceae s.l."}]"#,
+        ));
+        assert!(parse_json(&render_summary(&events, SummaryFormat::Json)).is_ok());
+    }
+
+    #[test]
+    fn test_render_json_keys_and_types() {
+        let value = parse_json(r#"{"taxons":{"added":[],"removed":[],"modified":[{"id":"t1","a\"b":{"old":1,"new":"x \"y\""},"flag":{"old":true,"new":null}}]}}"#).unwrap();
+        let json = render_summary(&summarize(&value), SummaryFormat::Json);
+        assert_eq!(json, concat!(
+            r#"[{"event":"fieldChanged","collection":"taxons","id":"t1","field":"a\"b","old":1,"new":"x \"y\""},"#,
+            r#"{"event":"fieldChanged","collection":"taxons","id":"t1","field":"flag","old":true,"new":null}]"#,
+        ));
+        assert!(parse_json(&json).is_ok());
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use crate::errors::{FieldError, JsonDiffError};
+use crate::hazo::Dataset;
+use crate::json::{parse_json, JsonValue};
+use crate::jsondiff::json_value_to_string;
+
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum IssueKind {
+    InvalidField,
+    DuplicateId,
+    DanglingReference,
+    Cycle,
+    PathMismatch,
+    ChildMissing,
+}
+
+impl IssueKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IssueKind::InvalidField => "invalidField",
+            IssueKind::DuplicateId => "duplicateId",
+            IssueKind::DanglingReference => "danglingReference",
+            IssueKind::Cycle => "cycle",
+            IssueKind::PathMismatch => "pathMismatch",
+            IssueKind::ChildMissing => "childMissing",
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Debug))]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub collection: String,
+    pub id: String,
+    pub field: String,
+    pub detail: String,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, collection: &str, id: &str, field: &str, detail: &str) -> Self {
+        Issue {
+            kind,
+            collection: collection.to_string(),
+            id: id.to_string(),
+            field: field.to_string(),
+            detail: detail.to_string(),
+        }
+    }
+
+    fn from_field_error(e: &FieldError) -> Self {
+        Issue::new(IssueKind::InvalidField, "", "", &e.path, &e.kind.to_string())
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            IssueKind::InvalidField => write!(f, "{}: {}", self.field, self.detail),
+            IssueKind::DuplicateId => write!(f, "{}/{}: duplicate id", self.collection, self.id),
+            IssueKind::DanglingReference => write!(f, "{}/{}: {} references unknown id '{}'", self.collection, self.id, self.field, self.detail),
+            IssueKind::Cycle => write!(f, "{}/{}: cycle in hierarchy: {}", self.collection, self.id, self.detail),
+            IssueKind::PathMismatch => write!(f, "{}/{}: path does not match parent '{}'", self.collection, self.id, self.detail),
+            IssueKind::ChildMissing => write!(f, "{}/{}: not listed in children of parent '{}'", self.collection, self.id, self.detail),
+        }
+    }
+}
+
+struct Node<'d, 'a> {
+    id: &'a str,
+    path: &'d [&'a str],
+    children: &'d [&'a str],
+}
+
+pub fn validate(dataset: &Dataset) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let taxons = index_ids("taxons", dataset.taxons.iter().map(|t| t.id), &mut issues);
+    let characters = index_ids("characters", dataset.characters.iter().map(|c| c.id), &mut issues);
+    let states = index_ids("states", dataset.states.iter().map(|s| s.id), &mut issues);
+    index_ids("books", dataset.books.iter().map(|b| b.id), &mut issues);
+
+    let taxon_nodes: Vec<Node> = dataset.taxons.iter().map(|t| Node { id: t.id, path: &t.path, children: &t.children }).collect();
+    check_hierarchy("taxons", &taxon_nodes, &taxons, &mut issues);
+    for t in &dataset.taxons {
+        for (i, d) in t.descriptions.iter().enumerate() {
+            let field = format!("descriptions/{}/descriptorId", i);
+            check_refs("taxons", t.id, &field, [d.descriptor_id], &characters, &mut issues);
+            let field = format!("descriptions/{}/statesIds", i);
+            check_refs("taxons", t.id, &field, d.states_ids.iter().copied(), &states, &mut issues);
+        }
+    }
+
+    let character_nodes: Vec<Node> = dataset.characters.iter().map(|c| Node { id: c.id, path: &c.path, children: &c.children }).collect();
+    check_hierarchy("characters", &character_nodes, &characters, &mut issues);
+    for c in &dataset.characters {
+        check_refs("characters", c.id, "states", c.states.iter().copied(), &states, &mut issues);
+        check_refs("characters", c.id, "inherentStateId", c.inherent_state_id, &states, &mut issues);
+        check_refs("characters", c.id, "inapplicableStatesIds", c.inapplicable_states_ids.iter().copied(), &states, &mut issues);
+        check_refs("characters", c.id, "requiredStatesIds", c.required_states_ids.iter().copied(), &states, &mut issues);
+    }
+    issues
+}
+
+pub fn validate_json_str(input: &str) -> Result<Vec<Issue>, JsonDiffError> {
+    let value = parse_json(input)?;
+    match Dataset::from_json(&value) {
+        Ok(dataset) => Ok(validate(&dataset)),
+        Err(errors) => Ok(errors.iter().map(Issue::from_field_error).collect()),
+    }
+}
+
+pub fn issues_to_json(issues: &[Issue]) -> String {
+    let mut s = String::from("[");
+    for (i, issue) in issues.iter().enumerate() {
+        if i > 0 { s.push(','); }
+        s.push_str("{\"kind\":\"");
+        s.push_str(issue.kind.name());
+        s.push('"');
+        for (key, value) in [("collection", &issue.collection), ("id", &issue.id), ("field", &issue.field), ("detail", &issue.detail)] {
+            if !value.is_empty() {
+                s.push_str(",\"");
+                s.push_str(key);
+                s.push_str("\":");
+                s.push_str(&json_value_to_string(&JsonValue::String(value)));
+            }
+        }
+        s.push('}');
+    }
+    s.push(']');
+    s
+}
+
+pub fn issues_to_text(issues: &[Issue]) -> String {
+    let mut s = String::new();
+    for issue in issues {
+        s.push_str(&issue.to_string());
+        s.push('\n');
+    }
+    s
+}
+
+fn index_ids<'a>(collection: &str, ids: impl Iterator<Item = &'a str>, issues: &mut Vec<Issue>) -> HashMap<&'a str, usize> {
+    let mut index = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (i, id) in ids.enumerate() {
+        if index.insert(id, i).is_some() && !duplicates.contains(&id) {
+            duplicates.push(id);
+        }
+    }
+    for id in duplicates {
+        issues.push(Issue::new(IssueKind::DuplicateId, collection, id, "id", ""));
+    }
+    index
+}
+
+fn check_refs<'a>(
+    collection: &str,
+    id: &str,
+    field: &str,
+    refs: impl IntoIterator<Item = &'a str>,
+    targets: &HashMap<&str, usize>,
+    issues: &mut Vec<Issue>,
+) {
+    for r in refs {
+        if !targets.contains_key(r) {
+            issues.push(Issue::new(IssueKind::DanglingReference, collection, id, field, r));
+        }
+    }
+}
+
+fn check_hierarchy(collection: &str, nodes: &[Node], index: &HashMap<&str, usize>, issues: &mut Vec<Issue>) {
+    for node in nodes {
+        check_refs(collection, node.id, "path", node.path.iter().copied(), index, issues);
+        check_refs(collection, node.id, "children", node.children.iter().copied(), index, issues);
+        if let Some(parent) = node.path.last().and_then(|p| index.get(p)).map(|&i| &nodes[i])
+            && !parent.children.contains(&node.id) {
+            issues.push(Issue::new(IssueKind::ChildMissing, collection, node.id, "path", parent.id));
+        }
+        for child in node.children.iter().filter_map(|c| index.get(c)).map(|&i| &nodes[i]) {
+            let expected_len = node.path.len() + 1;
+            if child.path.len() != expected_len || child.path[..node.path.len()] != *node.path || child.path[node.path.len()] != node.id {
+                issues.push(Issue::new(IssueKind::PathMismatch, collection, child.id, "path", node.id));
+            }
+        }
+    }
+    find_cycles(collection, nodes, index, issues);
+}
+
+// Recherche des cycles en suivant les enfants (0 = non visité, 1 = en cours, 2 = terminé). Le
+// parcours en profondeur garde sa propre pile : une longue chaîne ne doit pas épuiser celle du programme.
+fn find_cycles(collection: &str, nodes: &[Node], index: &HashMap<&str, usize>, issues: &mut Vec<Issue>) {
+    let mut state = vec![0u8; nodes.len()];
+    // Nœuds en cours de visite, avec le rang du prochain enfant à examiner
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for root in 0..nodes.len() {
+        if state[root] != 0 {
+            continue;
+        }
+        state[root] = 1;
+        stack.push((root, 0));
+        while let Some(&mut (i, ref mut next)) = stack.last_mut() {
+            let Some(child) = nodes[i].children.get(*next) else {
+                stack.pop();
+                state[i] = 2;
+                continue;
+            };
+            *next += 1;
+            let Some(&c) = index.get(child) else { continue };
+            match state[c] {
+                0 => {
+                    state[c] = 1;
+                    stack.push((c, 0));
+                }
+                1 => {
+                    let start = stack.iter().position(|&(s, _)| s == c).unwrap_or(0);
+                    let mut cycle: Vec<&str> = stack[start..].iter().map(|&(s, _)| nodes[s].id).collect();
+                    cycle.push(nodes[c].id);
+                    issues.push(Issue::new(IssueKind::Cycle, collection, nodes[c].id, "children", &cycle.join(" > ")));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<String> {
+        validate_json_str(input).unwrap().iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn test_valid_dataset() {
+        let input = r#"{
+            "taxons": [
+                {"id": "t0", "name": "Root", "path": [], "children": ["t1"]},
+                {"id": "t1", "name": "A", "path": ["t0"], "children": [],
+                 "descriptions": [{"descriptorId": "c1", "statesIds": ["s1"]}]}
+            ],
+            "characters": [{"id": "c1", "name": "Color", "states": ["s1"]}],
+            "states": [{"id": "s1", "name": "Red"}],
+            "books": [{"id": "b1", "label": "Flora"}]
+        }"#;
+        assert!(kinds(input).is_empty());
+    }
+
+    #[test]
+    fn test_dangling_and_duplicates() {
+        let input = r#"{
+            "taxons": [
+                {"id": "t1", "name": "A", "descriptions": [{"descriptorId": "c9", "statesIds": ["s9"]}]},
+                {"id": "t1", "name": "B"}
+            ],
+            "characters": [{"id": "c1", "name": "Color", "states": ["s1", "s2"], "inherentStateId": "s3"}],
+            "states": [{"id": "s1", "name": "Red"}]
+        }"#;
+        assert_eq!(kinds(input), vec![
+            "taxons/t1: duplicate id",
+            "taxons/t1: descriptions/0/descriptorId references unknown id 'c9'",
+            "taxons/t1: descriptions/0/statesIds references unknown id 's9'",
+            "characters/c1: states references unknown id 's2'",
+            "characters/c1: inherentStateId references unknown id 's3'",
+        ]);
+    }
+
+    #[test]
+    fn test_hierarchy_inconsistencies() {
+        let input = r#"{
+            "taxons": [
+                {"id": "t0", "name": "Root", "path": [], "children": ["t1"]},
+                {"id": "t1", "name": "A", "path": ["t2"], "children": []},
+                {"id": "t2", "name": "B", "path": ["t0"], "children": []}
+            ]
+        }"#;
+        assert_eq!(kinds(input), vec![
+            "taxons/t1: path does not match parent 't0'",
+            "taxons/t1: not listed in children of parent 't2'",
+            "taxons/t2: not listed in children of parent 't0'",
+        ]);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let input = r#"{
+            "taxons": [
+                {"id": "t1", "name": "A", "path": ["t2"], "children": ["t2"]},
+                {"id": "t2", "name": "B", "path": ["t1"], "children": ["t1"]}
+            ]
+        }"#;
+        let issues = validate_json_str(input).unwrap();
+        let cycles: Vec<String> = issues.iter().filter(|i| i.kind == IssueKind::Cycle).map(|i| i.to_string()).collect();
+        assert_eq!(cycles, vec!["taxons/t1: cycle in hierarchy: t1 > t2 > t1"]);
+    }
+
+    #[test]
+    fn test_cycle_in_long_chain() {
+        let n = 50_000;
+        let taxons: Vec<String> = (0..n).map(|i| format!(r#"{{"id":"t{}","name":"T","path":[],"children":["t{}"]}}"#, i, (i + 1) % n)).collect();
+        let input = format!(r#"{{"taxons":[{}]}}"#, taxons.join(","));
+        let issues = validate_json_str(&input).unwrap();
+        let cycles: Vec<&Issue> = issues.iter().filter(|i| i.kind == IssueKind::Cycle).collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].id, "t0");
+        assert_eq!(cycles[0].detail.matches(" > ").count(), n);
+    }
+
+    #[test]
+    fn test_field_errors_and_json_output() {
+        let issues = validate_json_str(r#"{"taxons":[{"id":"t1"}]}"#).unwrap();
+        assert_eq!(issues_to_json(&issues), "[{\"kind\":\"invalidField\",\"field\":\"/taxons/0/name\",\"detail\":\"missing field\"}]");
+        assert_eq!(issues_to_text(&issues), "/taxons/0/name: missing field\n");
+    }
+}
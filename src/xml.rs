@@ -0,0 +1,327 @@
+use crate::errors::{JsonDiffError, JsonDiffErrorType};
+
+pub fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn xml_unescape(text: &str) -> Result<String, JsonDiffError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidXml))? + start;
+        let c = match &rest[start + 1..end] {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            entity => {
+                let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                };
+                code.and_then(char::from_u32).ok_or(JsonDiffError::new(JsonDiffErrorType::InvalidXml))?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg_attr(test, derive(Debug))]
+pub struct XmlElement {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<XmlElement>,
+    pub text: String,
+}
+
+impl XmlElement {
+    // Les préfixes d'espace de noms sont ignorés
+    pub fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.local_name() == name)
+    }
+
+    pub fn children_named<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s XmlElement> + 's {
+        self.children.iter().filter(move |c| c.local_name() == name)
+    }
+
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|c| c.text.as_str())
+    }
+}
+
+// Profondeur d'imbrication maximale : un document SDD en reste très loin, et la limite protège
+// la pile lors de l'analyse récursive comme de la libération de l'arbre
+const MAX_DEPTH: usize = 256;
+
+struct XmlParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn error() -> JsonDiffError {
+        JsonDiffError::new(JsonDiffErrorType::InvalidXml)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, JsonDiffError> {
+        let rest = self.rest();
+        let i = rest.find(end).ok_or(Self::error())?;
+        self.pos += i + end.len();
+        Ok(&rest[..i])
+    }
+
+    // Déclaration, instructions de traitement, commentaires et DOCTYPE
+    fn skip_misc(&mut self) -> Result<(), JsonDiffError> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, JsonDiffError> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/')).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(Self::error());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn element(&mut self, depth: usize) -> Result<XmlElement, JsonDiffError> {
+        if depth > MAX_DEPTH || !self.rest().starts_with('<') {
+            return Err(Self::error());
+        }
+        self.pos += 1;
+        let name = self.name()?.to_string();
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(XmlElement { name, attrs, children: Vec::new(), text: String::new() });
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(Self::error());
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(Self::error()),
+            };
+            self.pos += 1;
+            let raw = self.skip_past(if quote == '"' { "\"" } else { "'" })?;
+            attrs.push((key, xml_unescape(raw)?));
+        }
+
+        let mut children = Vec::new();
+        let mut text = String::new();
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(Self::error());
+            }
+            if rest.starts_with("</") {
+                self.pos += 2;
+                if self.name()? != name {
+                    return Err(Self::error());
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(Self::error());
+                }
+                self.pos += 1;
+                break;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                text.push_str(self.skip_past("]]>")?);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                children.push(self.element(depth + 1)?);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                text.push_str(&xml_unescape(&rest[..len])?);
+                self.pos += len;
+            }
+        }
+        let text = if children.is_empty() { text } else { text.trim().to_string() };
+        Ok(XmlElement { name, attrs, children, text })
+    }
+}
+
+// Analyse un document XML en arbre d'éléments ; suffisant pour les échanges SDD
+pub fn parse_xml(input: &str) -> Result<XmlElement, JsonDiffError> {
+    let mut parser = XmlParser { input, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element(1)?;
+    parser.skip_misc()?;
+    if !parser.rest().is_empty() {
+        return Err(XmlParser::error());
+    }
+    Ok(root)
+}
+
+pub struct XmlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl Default for XmlWriter {
+    fn default() -> Self {
+        XmlWriter::new()
+    }
+}
+
+impl XmlWriter {
+    pub fn new() -> Self {
+        XmlWriter { out: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"), depth: 0 }
+    }
+
+    fn start_tag(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push('<');
+        self.out.push_str(tag);
+        for (name, value) in attrs {
+            self.out.push(' ');
+            self.out.push_str(name);
+            self.out.push_str("=\"");
+            self.out.push_str(&xml_escape(value));
+            self.out.push('"');
+        }
+    }
+
+    pub fn open(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.start_tag(tag, attrs);
+        self.out.push_str(">\n");
+        self.depth += 1;
+    }
+
+    pub fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str("</");
+        self.out.push_str(tag);
+        self.out.push_str(">\n");
+    }
+
+    pub fn empty(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.start_tag(tag, attrs);
+        self.out.push_str("/>\n");
+    }
+
+    pub fn text(&mut self, tag: &str, attrs: &[(&str, &str)], text: &str) {
+        self.start_tag(tag, attrs);
+        self.out.push('>');
+        self.out.push_str(&xml_escape(text));
+        self.out.push_str("</");
+        self.out.push_str(tag);
+        self.out.push_str(">\n");
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_writer() {
+        let mut w = XmlWriter::new();
+        w.open("Root", &[("id", "a&b")]);
+        w.text("Label", &[], "<Ficus> & \"co\"");
+        w.empty("Ref", &[("ref", "x")]);
+        w.close("Root");
+        assert_eq!(w.finish(), concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<Root id=\"a&amp;b\">\n",
+            "  <Label>&lt;Ficus&gt; &amp; &quot;co&quot;</Label>\n",
+            "  <Ref ref=\"x\"/>\n",
+            "</Root>\n",
+        ));
+    }
+
+    #[test]
+    fn test_parse_xml() {
+        let root = parse_xml(concat!(
+            "<?xml version=\"1.0\"?>\n<!-- dataset -->\n",
+            "<sdd:Root xmlns:sdd=\"urn:x\" id='a&amp;b'>\n",
+            "  <Label>&lt;Ficus&gt; &#233;t&#xE9; <![CDATA[<b>]]></Label>\n",
+            "  <Ref ref=\"x\"/>\n",
+            "</sdd:Root>\n",
+        )).unwrap();
+        assert_eq!(root.local_name(), "Root");
+        assert_eq!(root.attr("id"), Some("a&b"));
+        assert_eq!(root.child_text("Label"), Some("<Ficus> été <b>"));
+        assert_eq!(root.children_named("Ref").next().and_then(|r| r.attr("ref")), Some("x"));
+        assert!(parse_xml("<Root><Label></Root>").is_err());
+        assert!(parse_xml("<Root>&unknown;</Root>").is_err());
+        assert!(parse_xml("<Root/><Other/>").is_err());
+    }
+
+    #[test]
+    fn test_parse_xml_depth_limit() {
+        let nested = |depth: usize| format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(parse_xml(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse_xml(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse_xml(&"<a>".repeat(1_000_000)).is_err());
+    }
+}